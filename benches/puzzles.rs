@@ -0,0 +1,83 @@
+//! Timing for inputs shaped like real Advent of Code puzzles, mostly
+//! exercising the primitive parsers. Run with `cargo bench --bench puzzles`.
+
+use aoc_parse::{parser, prelude::*};
+use criterion::{criterion_group, criterion_main, Criterion};
+
+/// A cheap deterministic sequence of numbers, so the inputs look random
+/// without depending on a random number generator.
+fn numbers(n: usize) -> impl Iterator<Item = u64> {
+    (0..n as u64).map(|i| i.wrapping_mul(2_654_435_761) % 1_000_003)
+}
+
+fn puzzles(c: &mut Criterion) {
+    let mut group = c.benchmark_group("puzzles");
+    group.sample_size(20);
+
+    // A long list of numbers, one per line.
+    let input: String = numbers(100_000).map(|n| format!("{n}\n")).collect();
+    let p = parser!(lines(u64));
+    group.bench_function("lines(u64)", |b| b.iter(|| p.parse(&input).unwrap()));
+
+    // Comma-separated signed numbers on one line.
+    let input: String = numbers(100_000)
+        .map(|n| format!("{}", n as i64 - 500_000))
+        .collect::<Vec<_>>()
+        .join(",")
+        + "\n";
+    let p = parser!(line(repeat_sep(i64, ",")));
+    group.bench_function("line(repeat_sep(i64, \",\"))", |b| {
+        b.iter(|| p.parse(&input).unwrap())
+    });
+
+    // Instructions with several numbers per line.
+    let input: String = numbers(30_000)
+        .map(|n| format!("move {} from {} to {}\n", n % 50, n % 9 + 1, n % 7 + 1))
+        .collect();
+    let p = parser!(lines("move " usize " from " usize " to " usize));
+    group.bench_function("lines(\"move \" usize ...)", |b| {
+        b.iter(|| p.parse(&input).unwrap())
+    });
+
+    // Groups of numbers separated by blank lines.
+    let input: String = numbers(100_000)
+        .enumerate()
+        .map(|(i, n)| {
+            if i % 8 == 7 {
+                format!("{n}\n\n")
+            } else {
+                format!("{n}\n")
+            }
+        })
+        .collect();
+    let p = parser!(sections(lines(u32)));
+    group.bench_function("sections(lines(u32))", |b| {
+        b.iter(|| p.parse(&input).unwrap())
+    });
+
+    // Hexadecimal color codes.
+    let input: String = numbers(50_000)
+        .map(|n| format!("#{:06x} {}\n", n * 16 % 0x1000000, n % 100))
+        .collect();
+    let p = parser!(lines("#" u32_hex " " u8));
+    group.bench_function("lines(\"#\" u32_hex \" \" u8)", |b| {
+        b.iter(|| p.parse(&input).unwrap())
+    });
+
+    // A grid of digits.
+    let input: String = numbers(100)
+        .map(|n| {
+            let row: String = numbers(100)
+                .map(|m| char::from(b'0' + ((n + m) % 10) as u8))
+                .collect();
+            row + "\n"
+        })
+        .collect();
+    let p = parser!(lines(digit+));
+    group.bench_function("lines(digit+)", |b| b.iter(|| p.parse(&input).unwrap()));
+
+    group.finish();
+}
+
+criterion_group!(benches, puzzles);
+criterion_main!(benches);
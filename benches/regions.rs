@@ -0,0 +1,56 @@
+//! Timing for `line()`/`section()` boundaries and error tracking on large
+//! inputs. Run with `cargo bench --bench regions`.
+
+use aoc_parse::{parser, prelude::*};
+use criterion::{criterion_group, criterion_main, Criterion};
+
+/// About 1 MB of text: one number per line.
+fn number_lines() -> String {
+    (0..150_000)
+        .map(|i| format!("{}\n", i * 7919 % 1_000_000))
+        .collect()
+}
+
+/// About 1 MB of text in sections of 10 lines each.
+fn number_sections() -> String {
+    (0..15_000)
+        .map(|s| {
+            let lines: String = (0..10).map(|i| format!("{}\n", s * 10 + i)).collect();
+            lines + "\n"
+        })
+        .collect()
+}
+
+fn regions(c: &mut Criterion) {
+    let lines_input = number_lines();
+    let sections_input = number_sections();
+
+    let mut group = c.benchmark_group("regions");
+    group.sample_size(20);
+
+    let p = parser!(lines(u64));
+    group.bench_function("lines(u64)", |b| b.iter(|| p.parse(&lines_input).unwrap()));
+
+    // Every line first fails to match `"-"`, reporting an error.
+    let p = parser!(lines({"-" => 0, n:u64 => n}));
+    group.bench_function("lines({\"-\", u64}) (an error per line)", |b| {
+        b.iter(|| p.parse(&lines_input).unwrap())
+    });
+
+    let p = parser!(sections(lines(u64)));
+    group.bench_function("sections(lines(u64))", |b| {
+        b.iter(|| p.parse(&sections_input).unwrap())
+    });
+
+    // The error is at the very end of the input.
+    let bad = lines_input.clone() + "x\n";
+    let p = parser!(lines(u64));
+    group.bench_function("lines(u64), error at end", |b| {
+        b.iter(|| p.parse(&bad).unwrap_err().to_string())
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, regions);
+criterion_main!(benches);
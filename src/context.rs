@@ -1,8 +1,8 @@
 //! Mainly error tracking for the overall parse.
 
-use std::{any::Any, collections::HashMap};
+use std::{any::Any, cell::OnceCell, collections::HashMap, rc::Rc, sync::Arc};
 
-use crate::ParseError;
+use crate::{line_index::LineIndex, trace::Tracer, ParseError};
 
 /// Error type for when an error has been reported to ParseContext.
 ///
@@ -40,7 +40,7 @@ pub struct Reported;
 /// -   `u32` returns success, matching `4`.
 ///
 /// -   `line(u32)` reports an error to the context (at line 4 column 2) and
-///      returns `Reported`, because `u32` didn't match the entire line.
+///     returns `Reported`, because `u32` didn't match the entire line.
 ///
 /// -   `line(u32)+` then *discards* the `Reported` error, backtracks,
 ///     and returns a successful match for the first 3 lines.
@@ -66,8 +66,21 @@ pub struct Reported;
 /// try operator.
 pub struct ParseContext<'parse> {
     source: &'parse str,
+    /// The whole input, of which `source` is a slice starting at `base`.
+    input: &'parse str,
+    base: usize,
+    /// A shared copy of `input`, created the first time `str_slice()` needs
+    /// one. Contexts for slices of the input and detached contexts share the
+    /// same cell, so the input is copied at most once per parse.
+    shared_input: Rc<OnceCell<Arc<str>>>,
+    /// Where the lines start in `input`, computed the first time a `line()`,
+    /// a `section()` or an error needs it. Shared like `shared_input`.
+    line_index: Rc<OnceCell<Arc<LineIndex>>>,
     foremost_error: Option<ParseError>,
     rule_sets: HashMap<usize, &'parse [Box<dyn Any>]>,
+    tracer: Option<Tracer>,
+    /// True inside `nocase()`: string and character literals ignore case.
+    ignore_case: bool,
 }
 
 impl<'parse> ParseContext<'parse> {
@@ -75,8 +88,22 @@ impl<'parse> ParseContext<'parse> {
     pub fn new(source: &'parse str) -> Self {
         ParseContext {
             source,
+            input: source,
+            base: 0,
+            shared_input: Rc::default(),
+            line_index: Rc::default(),
             foremost_error: None,
             rule_sets: HashMap::new(),
+            tracer: None,
+            ignore_case: false,
+        }
+    }
+
+    /// Create a `ParseContext` that records a trace of every match attempt.
+    pub(crate) fn new_tracing(source: &'parse str) -> Self {
+        ParseContext {
+            tracer: Some(Tracer::default()),
+            ..ParseContext::new(source)
         }
     }
 
@@ -85,17 +112,132 @@ impl<'parse> ParseContext<'parse> {
         self.source
     }
 
+    /// Create a `ParseContext` to parse `input`, which the caller already has
+    /// in an `Arc`, so that `str_slice()` can share it instead of copying it.
+    pub(crate) fn new_shared(input: &'parse Arc<str>) -> Self {
+        let context = ParseContext::new(input);
+        context
+            .shared_input
+            .set(input.clone())
+            .expect("new context has no shared input yet");
+        context
+    }
+
+    /// The whole input being parsed, as a shared string, and the offset of
+    /// `self.source()` within it. The input is copied into an `Arc` the first
+    /// time this is called during a parse.
+    pub(crate) fn shared_input(&self) -> (Arc<str>, usize) {
+        let input = self.input;
+        let shared = self.shared_input.get_or_init(|| Arc::from(input)).clone();
+        (shared, self.base)
+    }
+
+    fn line_index(&self) -> &LineIndex {
+        let input = self.input;
+        self.line_index
+            .get_or_init(|| Arc::new(LineIndex::new(input)))
+    }
+
+    /// True if `start` is at the start of a line in `self.source()`.
+    pub(crate) fn is_line_start(&self, start: usize) -> bool {
+        start == 0 || self.line_index().is_line_start(self.base + start)
+    }
+
+    /// True if `start` is at the start of a section in `self.source()`: at
+    /// the start of `self.source()`, or just after a blank line.
+    pub(crate) fn is_section_start(&self, start: usize) -> bool {
+        start == 0 || (self.is_line_start(start) && (start == 1 || self.is_line_start(start - 1)))
+    }
+
+    /// The offset of the first `"\n\n"` in `self.source()` at or after
+    /// `start`.
+    pub(crate) fn find_blank_line(&self, start: usize) -> Option<usize> {
+        let (base, len) = (self.base, self.source.len());
+        self.line_index()
+            .next_blank_line(base + start, base + len)
+            .map(|i| i - base)
+    }
+
     /// Extract the error. Use this only after receiving `Reported` from an
     /// operation on the context.
     ///
     /// # Panics
     ///
     /// If no error has been reported on this context.
-    pub fn into_reported_error(self) -> ParseError {
+    pub fn into_reported_error(mut self) -> ParseError {
+        let err = self
+            .foremost_error
+            .take()
+            .expect("a parse error should have been reported");
+        self.attach_input(err)
+    }
+
+    /// Like `into_reported_error`, but the error doesn't have the source text
+    /// attached.
+    fn take_reported_error(self) -> ParseError {
         self.foremost_error
             .expect("a parse error should have been reported")
     }
 
+    /// Make a new context for the same source text and rule sets, but with no
+    /// errors and no trace.
+    ///
+    /// Because `ParseContext` is covariant, the new context can be used with
+    /// a shorter lifetime than `'parse`. `then()` uses this to match parsers
+    /// it creates during matching, which don't live that long.
+    pub(crate) fn detach(&self) -> ParseContext<'parse> {
+        ParseContext {
+            source: self.source,
+            input: self.input,
+            base: self.base,
+            shared_input: self.shared_input.clone(),
+            line_index: self.line_index.clone(),
+            foremost_error: None,
+            rule_sets: self.rule_sets.clone(),
+            tracer: None,
+            ignore_case: self.ignore_case,
+        }
+    }
+
+    /// True if literals should match without regard to case.
+    pub(crate) fn ignore_case(&self) -> bool {
+        self.ignore_case
+    }
+
+    /// Turn case-insensitive matching of literals on or off. Returns the old
+    /// setting.
+    pub(crate) fn set_ignore_case(&mut self, ignore_case: bool) -> bool {
+        std::mem::replace(&mut self.ignore_case, ignore_case)
+    }
+
+    /// Take the foremost error reported so far, if any, without the source
+    /// text attached.
+    pub(crate) fn take_error(&mut self) -> Option<ParseError> {
+        self.foremost_error.take()
+    }
+
+    /// The trace recorder, if this context is recording a trace.
+    pub(crate) fn tracer(&mut self) -> Option<&mut Tracer> {
+        self.tracer.as_mut()
+    }
+
+    /// Split this context into the foremost error reported so far and the
+    /// recorded trace.
+    pub(crate) fn into_error_and_trace(mut self) -> (Option<ParseError>, Option<Tracer>) {
+        let err = self.foremost_error.take().map(|err| self.attach_input(err));
+        (err, self.tracer)
+    }
+
+    /// Attach the whole input, and the index of its lines, to an error made
+    /// while parsing `self.source()`. The input is shared, not copied, so
+    /// this is cheap even for many errors, as in `lines_lenient()`.
+    fn attach_input(&self, err: ParseError) -> ParseError {
+        let (input, _) = self.shared_input();
+        err.adjust_location(self.base)
+            .with_shared_source(input)
+            .with_line_starts(self.line_index().line_starts())
+    }
+
     /// Create a temporary child context for parsing a slice of `self.source`.
     /// Invoke the given closure `f` with that temporary context. Propagate
     /// errors to `self`.
@@ -107,29 +249,65 @@ impl<'parse> ParseContext<'parse> {
     /// not narrower. That's the lifetime of `source`, which is the same for
     /// the slice as for the whole.
     pub(crate) fn with_slice<F, T>(&mut self, start: usize, end: usize, f: F) -> Result<T, Reported>
+    where
+        F: for<'a> FnOnce(&'a mut Self) -> Result<T, Reported>,
+    {
+        self.slice_impl(start, end, f).map_err(|err| {
+            // Not `self.report()`: the error is already in the trace.
+            self.keep_if_foremost(err);
+            Reported
+        })
+    }
+
+    /// Like `with_slice`, but if `f` fails, return the error rather than
+    /// reporting it to `self`. The error is located in the whole input, not
+    /// `self.source`, which may be only part of it, as inside `section()`.
+    pub(crate) fn try_slice<F, T>(
+        &mut self,
+        start: usize,
+        end: usize,
+        f: F,
+    ) -> Result<T, ParseError>
+    where
+        F: for<'a> FnOnce(&'a mut Self) -> Result<T, Reported>,
+    {
+        self.slice_impl(start, end, f)
+            .map_err(|err| self.attach_input(err))
+    }
+
+    /// Shared implementation of `with_slice` and `try_slice`. On failure,
+    /// returns the error without the source text attached.
+    fn slice_impl<F, T>(&mut self, start: usize, end: usize, f: F) -> Result<T, ParseError>
     where
         F: for<'a> FnOnce(&'a mut Self) -> Result<T, Reported>,
     {
         let mut inner_context = ParseContext {
             source: &self.source[start..end],
+            input: self.input,
+            base: self.base + start,
+            shared_input: self.shared_input.clone(),
+            line_index: self.line_index.clone(),
             foremost_error: None,
             rule_sets: HashMap::new(),
+            tracer: None,
+            ignore_case: self.ignore_case,
         };
 
         std::mem::swap(&mut self.rule_sets, &mut inner_context.rule_sets);
+        std::mem::swap(&mut self.tracer, &mut inner_context.tracer);
+        if let Some(tracer) = &mut inner_context.tracer {
+            tracer.base += start;
+        }
 
         let r = f(&mut inner_context);
 
+        if let Some(tracer) = &mut inner_context.tracer {
+            tracer.base -= start;
+        }
+        std::mem::swap(&mut self.tracer, &mut inner_context.tracer);
         std::mem::swap(&mut self.rule_sets, &mut inner_context.rule_sets);
 
-        if r.is_err() {
-            self.report(
-                inner_context
-                    .into_reported_error()
-                    .adjust_location(self.source, start),
-            );
-        }
-        r
+        r.map_err(|Reported| inner_context.take_reported_error().adjust_location(start))
     }
 
     /// Record an error.
@@ -145,15 +323,35 @@ impl<'parse> ParseContext<'parse> {
     /// we progressed as far as possible through the input string before
     /// failing.
     pub fn report(&mut self, err: ParseError) -> Reported {
+        if let Some(tracer) = &mut self.tracer {
+            tracer.error(err.location, err.reason().to_string());
+        }
+        self.keep_if_foremost(err);
+        Reported
+    }
+
+    fn keep_if_foremost(&mut self, err: ParseError) {
         if Some(err.location) > self.foremost_error.as_ref().map(|err| err.location) {
             self.foremost_error = Some(err);
         }
-        Reported
+    }
+
+    /// Replace the foremost error with an `expected {label}` error, if it is
+    /// located at `start`. Used by `label()` when its pattern fails without
+    /// getting past the first character.
+    pub(crate) fn relabel_error(&mut self, start: usize, label: &str) {
+        if self.foremost_error.as_ref().map(|err| err.location) == Some(start) {
+            let err = ParseError::new_expected(start, label);
+            if let Some(tracer) = &mut self.tracer {
+                tracer.error(start, err.reason().to_string());
+            }
+            self.foremost_error = Some(err);
+        }
     }
 
     /// Record a `foo expected` error.
     pub fn error_expected(&mut self, start: usize, expected: &str) -> Reported {
-        self.report(ParseError::new_expected(self.source(), start, expected))
+        self.report(ParseError::new_expected(start, expected))
     }
 
     /// Record an error when `FromStr::from_str` fails.
@@ -161,7 +359,7 @@ impl<'parse> ParseContext<'parse> {
         &mut self,
         start: usize,
         end: usize,
-        type_name: &'static str,
+        type_name: String,
         message: String,
     ) -> Reported {
         self.report(ParseError::new_from_str_failed(
@@ -173,9 +371,24 @@ impl<'parse> ParseContext<'parse> {
         ))
     }
 
+    /// Record an error when a `=>?` conversion fails.
+    pub(crate) fn error_conversion_failed(
+        &mut self,
+        start: usize,
+        end: usize,
+        message: String,
+    ) -> Reported {
+        self.report(ParseError::new_conversion_failed(
+            self.source(),
+            start,
+            end,
+            message,
+        ))
+    }
+
     /// Record an "extra unparsed text after match" error.
     pub fn error_extra(&mut self, location: usize) -> Reported {
-        self.report(ParseError::new_extra(self.source(), location))
+        self.report(ParseError::new_extra(location))
     }
 
     pub(crate) fn register_rule_set(
@@ -186,6 +399,33 @@ impl<'parse> ParseContext<'parse> {
         self.rule_sets.insert(rule_set_id, rule_parsers);
     }
 
+    /// True if any rule sets have been registered. Their parsers can only be
+    /// used on this thread.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn has_rule_sets(&self) -> bool {
+        !self.rule_sets.is_empty()
+    }
+
+    /// Capture what other threads need to parse slices of `self.source()`
+    /// the same way `with_slice` would: the whole input, its shared copy and
+    /// its line index, and the `nocase()` setting.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn share_source(&self) -> SharedSource<'parse> {
+        let (shared_input, base) = self.shared_input();
+        let input = self.input;
+        let line_index = self
+            .line_index
+            .get_or_init(|| Arc::new(LineIndex::new(input)))
+            .clone();
+        SharedSource {
+            input,
+            base,
+            shared_input,
+            line_index,
+            ignore_case: self.ignore_case,
+        }
+    }
+
     pub(crate) fn fetch_parser_for_rule(
         &self,
         rule_set_id: usize,
@@ -199,8 +439,42 @@ impl<'parse> ParseContext<'parse> {
     }
 }
 
+/// The parts of a `ParseContext` that can be sent to other threads. See
+/// `ParseContext::share_source`.
+#[cfg(feature = "rayon")]
+pub(crate) struct SharedSource<'parse> {
+    input: &'parse str,
+    base: usize,
+    shared_input: Arc<str>,
+    line_index: Arc<LineIndex>,
+    ignore_case: bool,
+}
+
+#[cfg(feature = "rayon")]
+impl<'parse> SharedSource<'parse> {
+    /// Make a new context for parsing `start..end` of the source of the
+    /// context this came from. Errors and values are located in the whole
+    /// input, as with `ParseContext::with_slice`, but the new context has no
+    /// rule sets and no trace.
+    pub(crate) fn slice(&self, start: usize, end: usize) -> ParseContext<'parse> {
+        let (start, end) = (self.base + start, self.base + end);
+        ParseContext {
+            source: &self.input[start..end],
+            input: self.input,
+            base: start,
+            shared_input: Rc::new(OnceCell::from(self.shared_input.clone())),
+            line_index: Rc::new(OnceCell::from(self.line_index.clone())),
+            foremost_error: None,
+            rule_sets: HashMap::new(),
+            tracer: None,
+            ignore_case: self.ignore_case,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
     use crate::parsers::{lines, sections, u64};
     use crate::testing::*;
 
@@ -217,7 +491,7 @@ mod tests {
         ";
 
         assert_parse_error(
-            &p,
+            p,
             example1,
             "matched part of the line, but not all of it at line 4 column 8",
         );
@@ -237,9 +511,20 @@ mod tests {
         ";
 
         assert_parse_error(
-            &p,
+            p,
             example2,
             "matched part of the line, but not all of it at line 6 column 2",
         );
     }
+
+    #[test]
+    fn test_line_index_is_shared() {
+        let context = ParseContext::new("ab\n\ncd\n");
+        assert!(context.is_section_start(4));
+        assert!(!context.is_line_start(1));
+
+        let detached = context.detach();
+        assert!(Rc::ptr_eq(&context.line_index, &detached.line_index));
+        assert!(detached.line_index.get().is_some());
+    }
 }
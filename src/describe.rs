@@ -0,0 +1,138 @@
+//! Pattern-like descriptions of parsers, for trace output and error messages.
+//!
+//! Parsers describe themselves by implementing [`Parser::fmt_pattern`]. A
+//! combinator writes its subparsers using the helpers in this module, which
+//! tell the subparser what kind of position it's in, so that the result can be
+//! read back as a pattern:
+//!
+//! -   In an *atom* position, like the operand of `*`, a concatenation like
+//!     `u64 "x"` has to be wrapped in parentheses.
+//!
+//! -   In an *arm* position, directly inside `{...}`, an alternation can leave
+//!     off its own braces, so that `{a, {b, c}}` prints as `{a, b, c}`.
+//!
+//! The position is passed along using the formatter's `#` and `+` flags, which
+//! parsers otherwise have no use for.
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::Parser;
+
+/// A pattern-like description of a parser, returned by [`Parser::describe`].
+pub struct Describe<'a, P: ?Sized> {
+    parser: &'a P,
+}
+
+impl<'a, P: ?Sized> Describe<'a, P> {
+    pub(crate) fn new(parser: &'a P) -> Self {
+        Describe { parser }
+    }
+}
+
+impl<P: Parser + ?Sized> Display for Describe<'_, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.parser.fmt_pattern(f)
+    }
+}
+
+/// Write a subpattern in an ordinary position, such as a function argument.
+pub(crate) fn write_pattern<P: Parser + ?Sized>(f: &mut Formatter<'_>, parser: &P) -> fmt::Result {
+    write!(f, "{}", parser.describe())
+}
+
+/// Write a subpattern in an atom position: the operand of a postfix quantifier.
+pub(crate) fn write_atom<P: Parser + ?Sized>(f: &mut Formatter<'_>, parser: &P) -> fmt::Result {
+    write!(f, "{:#}", parser.describe())
+}
+
+/// Write a subpattern that is an arm of an alternation.
+pub(crate) fn write_arm<P: Parser + ?Sized>(f: &mut Formatter<'_>, parser: &P) -> fmt::Result {
+    write!(f, "{:+}", parser.describe())
+}
+
+/// True if `f` is writing an atom position; a concatenation must be
+/// parenthesized.
+pub(crate) fn is_atom_position(f: &Formatter<'_>) -> bool {
+    f.alternate()
+}
+
+/// True if `f` is writing an arm of an alternation.
+pub(crate) fn is_arm_position(f: &Formatter<'_>) -> bool {
+    f.sign_plus()
+}
+
+/// Fallback description for parsers that don't implement `fmt_pattern`: the
+/// type name, without module paths.
+pub(crate) fn type_name<P: ?Sized>() -> String {
+    let full = std::any::type_name::<P>();
+    let mut out = String::with_capacity(full.len());
+    let mut segment_start = 0;
+    for (i, c) in full.char_indices() {
+        if !(c.is_alphanumeric() || c == '_' || c == ':') {
+            out.push_str(last_path_segment(&full[segment_start..i]));
+            out.push(c);
+            segment_start = i + c.len_utf8();
+        }
+    }
+    out.push_str(last_path_segment(&full[segment_start..]));
+    out
+}
+
+fn last_path_segment(path: &str) -> &str {
+    match path.rfind("::") {
+        Some(i) => &path[i + 2..],
+        None => path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::{alpha, char_of, i32_hex, line, lines, repeat_sep, section, u64};
+    use crate::prelude::string;
+    use crate::testing::*;
+
+    #[track_caller]
+    fn assert_describe<P: Parser>(parser: P, expected: &str) {
+        assert_eq!(parser.describe().to_string(), expected);
+    }
+
+    #[test]
+    fn test_type_name_strips_paths() {
+        assert_eq!(type_name::<Vec<String>>(), "Vec<String>");
+        assert_eq!(type_name::<&str>(), "&str");
+    }
+
+    #[test]
+    fn test_describe() {
+        assert_describe(u64, "u64");
+        assert_describe(i32_hex, "i32_hex");
+        assert_describe("x", "\"x\"");
+        assert_describe('\n', "'\\n'");
+        assert_describe(char_of("<>"), "char_of(\"<>\")");
+        assert_describe(empty(), "()");
+
+        let dims = sequence(u64, sequence("x", sequence(u64, sequence("x", u64))));
+        assert_describe(dims, "u64 \"x\" u64 \"x\" u64");
+        assert_describe(lines(dims), "lines(u64 \"x\" u64 \"x\" u64)");
+        assert_describe(star(line(dims)), "lines(u64 \"x\" u64 \"x\" u64)");
+        assert_describe(plus(line(u64)), "line(u64)+");
+        assert_describe(star(&section(lines(u64))), "sections(lines(u64))");
+        assert_describe(star(string("line(x)")), "string(\"line(x)\")*");
+
+        assert_describe(star(sequence(alpha, "-")), "(alpha \"-\")*");
+        assert_describe(plus(alpha), "alpha+");
+        assert_describe(opt(sequence("-", u64)), "(\"-\" u64)?");
+        assert_describe(opt(&empty()), "()?");
+        assert_describe(repeat_sep(u64, &empty()), "u64*");
+        assert_describe(repeat_sep(u64, ","), "repeat_sep(u64, \",\")");
+        assert_describe(string(plus(alpha)), "string(alpha+)");
+
+        assert_describe(alt("a", alt("b", "c")), "{\"a\", \"b\", \"c\"}");
+        assert_describe(alt(alt("a", "b"), "c"), "{\"a\", \"b\", \"c\"}");
+        assert_describe(
+            alt(sequence("a", u64), sequence("b", u64)),
+            "{\"a\" u64, \"b\" u64}",
+        );
+    }
+}
@@ -1,9 +1,15 @@
+use std::sync::Arc;
+
 use thiserror::Error;
 
+use crate::line_index::line_containing;
+
 #[derive(Clone, Debug, Error)]
 enum ParseErrorReason {
     #[error("extra unparsed text after match")]
     Extra,
+    #[error("input is not valid UTF-8")]
+    InvalidUtf8,
     #[error("line() can't match here because this is not at the start of a line")]
     NotAtLineStart,
     #[error("section() can't match here because this is not at the start of a section")]
@@ -17,23 +23,36 @@ enum ParseErrorReason {
     #[error("failed to parse {input:?} as type {type_name}: {message}")]
     FromStrFailed {
         input: String,
-        type_name: &'static str,
+        type_name: Box<str>,
         message: String,
     },
+    #[error("failed to convert {input:?}: {message}")]
+    ConversionFailed { input: String, message: String },
+    #[error("failed to read line {line}: {message}")]
+    Io { line: usize, message: String },
 }
 
 /// An error happened while trying to parse puzzle input or convert the matched
 /// characters to a Rust value.
 #[derive(Clone)]
 pub struct ParseError {
-    /// The puzzle input we were trying to parse.
-    pub source: String,
+    /// The puzzle input we were trying to parse. Errors from the same parse
+    /// share one copy of it.
+    pub source: Arc<str>,
 
     /// The byte offset into `source` where the elves detected a problem and
     /// could not go any further. This is guaranteed to be a char boundary in
     /// `source`.
     pub location: usize,
 
+    /// The number of lines of input before `source`. This is nonzero only for
+    /// errors from `parse_lines_from`, where `source` is a single line.
+    line_offset: usize,
+
+    /// The offsets of the starts of all lines in `source`, if the parser had
+    /// already computed them.
+    line_starts: Option<Arc<[usize]>>,
+
     reason: ParseErrorReason,
 }
 
@@ -41,15 +60,30 @@ impl std::fmt::Display for ParseError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let reason = &self.reason;
         let source = &self.source;
-        if self.location == source.len() {
+        if let ParseErrorReason::Io { .. } = reason {
+            write!(f, "{reason}")
+        } else if self.location == source.len() {
             write!(f, "{reason} at end of input")
         } else {
             let p = self.location.min(source.len());
-            let line_start = match source[..p].rfind('\n') {
-                Some(i) => i + 1,
-                None => 0,
+            let (line_index, line_start) = match &self.line_starts {
+                Some(line_starts) => {
+                    let i = line_containing(line_starts, p);
+                    (i, line_starts[i])
+                }
+                None => {
+                    let line_start = match source[..p].rfind('\n') {
+                        Some(i) => i + 1,
+                        None => 0,
+                    };
+                    let i = source.as_bytes()[..line_start]
+                        .iter()
+                        .filter(|&&b| b == b'\n')
+                        .count();
+                    (i, line_start)
+                }
             };
-            let line_num = source[..line_start].chars().filter(|c| *c == '\n').count() + 1;
+            let line_num = line_index + self.line_offset + 1;
             let column_num = source[line_start..p].chars().count() + 1;
             write!(f, "{reason} at line {line_num} column {column_num}")
         }
@@ -73,67 +107,131 @@ impl std::fmt::Debug for ParseError {
 impl std::error::Error for ParseError {}
 
 impl ParseError {
-    fn new(source: &str, location: usize, reason: ParseErrorReason) -> Self {
-        assert!(source.is_char_boundary(location));
+    /// Make an error without a copy of the source text. Errors are created
+    /// this way while parsing, since most are discarded; the source is
+    /// attached, using `with_source`, only to the error that is returned.
+    fn new(location: usize, reason: ParseErrorReason) -> Self {
         ParseError {
-            source: source.to_string(),
+            source: Arc::from(""),
             location,
+            line_offset: 0,
+            line_starts: None,
             reason,
         }
     }
 
-    pub(crate) fn new_extra(source: &str, location: usize) -> Self {
-        Self::new(source, location, ParseErrorReason::Extra)
+    /// Attach the text being parsed to an error made while parsing it.
+    pub(crate) fn with_source(self, source: &str) -> Self {
+        self.with_shared_source(Arc::from(source))
     }
 
-    pub(crate) fn new_bad_line_start(source: &str, location: usize) -> Self {
-        Self::new(source, location, ParseErrorReason::NotAtLineStart)
+    /// Like `with_source`, but shares a copy of the text the caller already
+    /// has.
+    pub(crate) fn with_shared_source(mut self, source: Arc<str>) -> Self {
+        assert!(source.is_char_boundary(self.location));
+        self.source = source;
+        self.line_starts = None;
+        self
     }
 
-    pub(crate) fn new_bad_section_start(source: &str, location: usize) -> Self {
-        Self::new(source, location, ParseErrorReason::NotAtSectionStart)
+    /// Attach the offsets of the starts of all lines in `self.source`, so
+    /// that displaying the error doesn't have to count them.
+    pub(crate) fn with_line_starts(mut self, line_starts: Arc<[usize]>) -> Self {
+        self.line_starts = Some(line_starts);
+        self
     }
 
-    pub(crate) fn new_line_extra(source: &str, location: usize) -> Self {
-        Self::new(source, location, ParseErrorReason::LineExtra)
+    /// Note that `self.source` is only part of the input, starting after
+    /// `line_offset` lines.
+    pub(crate) fn with_line_offset(mut self, line_offset: usize) -> Self {
+        self.line_offset = line_offset;
+        self
     }
 
-    pub(crate) fn new_section_extra(source: &str, location: usize) -> Self {
-        Self::new(source, location, ParseErrorReason::SectionExtra)
+    pub(crate) fn new_extra(location: usize) -> Self {
+        Self::new(location, ParseErrorReason::Extra)
     }
 
-    pub(crate) fn new_expected(source: &str, location: usize, expected: &str) -> Self {
-        Self::new(
-            source,
-            location,
-            ParseErrorReason::Expected(expected.to_string()),
-        )
+    /// Make an error for input bytes that aren't valid UTF-8. `source` is the
+    /// input, decoded lossily.
+    pub(crate) fn new_invalid_utf8(source: &str, location: usize) -> Self {
+        Self::new(location, ParseErrorReason::InvalidUtf8).with_source(source)
+    }
+
+    pub(crate) fn new_bad_line_start(location: usize) -> Self {
+        Self::new(location, ParseErrorReason::NotAtLineStart)
+    }
+
+    pub(crate) fn new_bad_section_start(location: usize) -> Self {
+        Self::new(location, ParseErrorReason::NotAtSectionStart)
+    }
+
+    pub(crate) fn new_line_extra(location: usize) -> Self {
+        Self::new(location, ParseErrorReason::LineExtra)
+    }
+
+    pub(crate) fn new_section_extra(location: usize) -> Self {
+        Self::new(location, ParseErrorReason::SectionExtra)
+    }
+
+    pub(crate) fn new_expected(location: usize, expected: &str) -> Self {
+        Self::new(location, ParseErrorReason::Expected(expected.to_string()))
     }
 
     pub(crate) fn new_from_str_failed(
         source: &str,
         start: usize,
         end: usize,
-        type_name: &'static str,
+        type_name: String,
         message: String,
     ) -> Self {
         Self::new(
-            source,
             start,
             ParseErrorReason::FromStrFailed {
                 input: source[start..end].to_string(),
-                type_name,
+                type_name: type_name.into_boxed_str(),
                 message,
             },
         )
     }
 
+    pub(crate) fn new_conversion_failed(
+        source: &str,
+        start: usize,
+        end: usize,
+        message: String,
+    ) -> Self {
+        Self::new(
+            start,
+            ParseErrorReason::ConversionFailed {
+                input: source[start..end].to_string(),
+                message,
+            },
+        )
+    }
+
+    /// Make an error for a failure to read the given line (counting from 1)
+    /// of the input.
+    pub(crate) fn new_io(line: usize, err: &std::io::Error) -> Self {
+        Self::new(
+            0,
+            ParseErrorReason::Io {
+                line,
+                message: err.to_string(),
+            },
+        )
+    }
+
+    /// The reason for the error, without the location.
+    pub(crate) fn reason(&self) -> &impl std::fmt::Display {
+        &self.reason
+    }
+
     /// This is used when a subparser is used on a slice of the original
     /// string. If the subparse fails, the error location is a position within
     /// the slice. This can be used, passing the start offset of the slice, to
     /// convert that to a position within the original string.
-    pub(crate) fn adjust_location(mut self, full_source: &str, offset: usize) -> Self {
-        self.source = full_source.to_string();
+    pub(crate) fn adjust_location(mut self, offset: usize) -> Self {
         self.location += offset;
         self
     }
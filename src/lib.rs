@@ -93,10 +93,48 @@
 //! Match an integer in base 2 or base 16. The `_hex` parsers allow both uppercase and lowercase
 //! digits `A`-`F`.
 //!
+//! `i8_oct`, `i16_oct`, `i32_oct`, `i64_oct`, `i128_oct`, `isize_oct`,
+//! `u8_oct`, `u16_oct`, `u32_oct`, `u64_oct`, `u128_oct`, `usize_oct` - Match an integer in base 8.
+//!
+//! <code>int_radix::&lt;<var>T</var>&gt;(<var>radix</var>)</code> - Match an integer in any base
+//! from 2 to 36, like `int_radix::<u64>(36)` for base-36 numbers, producing a `T`, which can be
+//! any primitive integer type. Bind it to a variable outside `parser!` and use the variable in the
+//! pattern.
+//!
+//! <code>int_auto::&lt;<var>T</var>&gt;()</code> - Match an integer with an optional `0x`, `0o`
+//! or `0b` prefix, like `0x1F`, `0b101`, or plain decimal `31`. Underscores between digits are
+//! allowed, as in Rust source code.
+//!
+//! <code>int_underscored::&lt;<var>T</var>&gt;()</code> - Match a decimal integer with optional
+//! underscores between digits, like `1_000_000`.
+//!
+//! <code>grouped_u64(<var>sep</var>)</code>, <code>grouped_i64(<var>sep</var>)</code> - Match a
+//! decimal integer with digits in groups of three separated by the character *sep*, like
+//! `1,000,000` for `grouped_u64(',')`.
+//!
+//! <code>int_lenient::&lt;<var>T</var>&gt;()</code> - Match a decimal integer, allowing spaces
+//! after the sign, as in `- 5`, and accepting the Unicode minus sign `−` as well as `-`.
+//!
+//! As with the other integer parsers, numbers too large for the type are an error.
+//!
+//! <code>digits_from::&lt;<var>T</var>&gt;(<var>digits</var>, <var>offset</var>)</code> - Match
+//! a number written with a custom set of digits, in base `digits.chars().count()`. Each digit is
+//! worth its index in *digits* plus *offset*, so `digits_from::<i64>("=-012", -2)` parses
+//! balanced base-5 "SNAFU" numbers.
+//!
 //! `f32` ,`f64` - These match a floating-point number written out using decimal digits, in [this
 //! format](https://doc.rust-lang.org/std/primitive.f64.html#impl-FromStr-for-f64). (No Advent of
 //! Code puzzle has ever hinged on floating-point numbers, but it doesn't hurt to be prepared.)
 //!
+//! `rational`, `big_rational` - Match a fraction like `1/4` or `-3/2`, or an integer like `7`, and
+//! produce an exact [`num_rational::Ratio<i64>`] or [`num_rational::BigRational`]. A zero
+//! denominator is an error. The `num_rational` crate is re-exported as `aoc_parse::num_rational`, so
+//! you don't need to depend on it to name these types.
+//!
+//! `decimal`, `big_decimal` - Match a decimal number like `3.25` or `-0.1`, optionally with an
+//! exponent like `1.5e-3`, and produce its exact value as a `Ratio<i64>` or `BigRational`. There's
+//! no rounding, unlike `f64`.
+//!
 //! `bool` - Matches either `true` or `false` and converts it to the corresponding `bool` value.
 //!
 //! `'x'` or `"hello"` - A Rust character or string, in quotes, is a pattern that matches that
@@ -145,6 +183,10 @@
 //! *pattern* to Rust values, producing a `Vec`. Any parts of the string matched by *separator* are
 //! not converted.
 //!
+//! <code>repeat_n(<var>pattern</var>, <var>n</var>)</code>,
+//! <code>repeat_sep_n(<var>pattern</var>, <var>separator</var>, <var>n</var>)</code> - Like `*` and
+//! `repeat_sep`, but match *pattern* exactly *n* times.
+//!
 //! ## Matching single characters
 //!
 //! `alpha`, `alnum`, `upper`, `lower` - Match single characters of various categories. (These use
@@ -153,6 +195,15 @@
 //! `digit`, `digit_bin`, `digit_hex` - Match a single ASCII character that's a digit in base 10,
 //! base 2, or base 16, respectively. The digit is converted to its numeric value, as a `usize`.
 //!
+//! <code>digit_radix::&lt;<var>T</var>&gt;(<var>radix</var>)</code> - Match a single digit in any
+//! base from 2 to 36 and convert it to its numeric value, as a `T`.
+//!
+//! `ascii_alpha`, `ascii_alnum`, `ascii_upper`, `ascii_lower` - Like `alpha` etc., but only ASCII
+//! characters match.
+//!
+//! `whitespace` - Match a whitespace character, including newlines. `punct` - Match an ASCII
+//! punctuation character, like `#` or `.`.
+//!
 //! `any_char` - Match the next character, no matter what it is (like `.` in a regular expression,
 //! except that `any_char` matches newline characters too).
 //!
@@ -161,6 +212,51 @@
 //! or `v`. Returns the index of the character within the list of options (in this case, `0`, `1`,
 //! `2`, or `3`).
 //!
+//! The options can also be a `String` built at run time.
+//!
+//! <code>char_class(<var>str</var>)</code> - Match any character in a character class, written
+//! like a regular expression character class without the brackets: `char_class("a-zA-Z_")`.
+//! Supports ranges, a leading `^`, and backslash escapes; see [`char_class`][prelude::char_class]
+//! for details. Returns the `char`.
+//!
+//! <code>none_of(<var>str</var>)</code> - Match any character that's *not* in *str*. Returns the
+//! `char`.
+//!
+//! <code>char_where(<var>noun</var>, <var>predicate</var>)</code> - Match any character for which
+//! the function *predicate* returns true. *noun* is used in error messages, as in `expected
+//! *noun*`.
+//!
+//! <code>exact(<var>text</var>)</code> - Match exactly the string *text*, like a string literal,
+//! except that *text* can be a `String` built at run time.
+//!
+//! <code>one_of_str(<var>words</var>)</code> - Match any one of the strings in *words*, which can
+//! be any list of strings, including a `Vec<String>` built from an earlier part of the input.
+//! Returns the index of the word that matched. Longer words are tried first; if the rest of the
+//! pattern fails to match, shorter ones are tried.
+//!
+//! <code>one_of_str_map(<var>pairs</var>)</code> - Like `one_of_str`, but *pairs* is a list of
+//! `(word, value)` pairs, and the result is the value for the word that matched. This is much
+//! faster than an alternation like `{"north" => N, "south" => S, ...}` with many arms.
+//!
+//! To parse an enum by the names of its variants, define it with [`enum_parser!`]. Then the name
+//! of the enum can be used as a pattern, as in `parser!(lines(Direction " " u32))`.
+//!
+//! ## Matching bytes
+//!
+//! Some puzzle inputs are grids of ASCII characters, and it's convenient to get them as `u8`
+//! values. These patterns can be used with [`Parser::parse_bytes`], which parses a `&[u8]` that
+//! holds UTF-8 text, or with ordinary `parse`.
+//!
+//! `any_byte` - Match any ASCII character, producing it as a `u8`.
+//!
+//! `byte_digit` - Match an ASCII decimal digit and convert it to its value, as a `u8`.
+//!
+//! <code>byte_of(<var>bytes</var>)</code> - Like `char_of`, but takes a byte string like `b".#"`.
+//! Only ASCII bytes match.
+//!
+//! `byte_line` - Match a line and produce its contents, without the newline, as a `Vec<u8>`. So
+//! `byte_line*` parses a grid of bytes.
+//!
 //! ## Matching multiple characters
 //!
 //! <code>string(<var>pattern</var>)</code> - Matches the given *pattern*, but instead of
@@ -169,6 +265,21 @@
 //! By default, `alpha+` returns a `Vec<char>`, and sometimes that is handy in AoC, but often it's
 //! better to have it return a `String`.
 //!
+//! <code>str_slice(<var>pattern</var>)</code> - Like `string`, but returns a [`StrSlice`] that
+//! shares the input text instead of copying it into a new `String`. A `StrSlice` derefs to `str`,
+//! and a `HashMap` with `StrSlice` keys can be indexed by `&str`.
+//!
+//! <code>nocase(<var>pattern</var>)</code> - Matches *pattern*, but string and character literals
+//! in it ignore case, so `parser!(nocase("Valve " alpha+))` matches `valve aa` and `VALVE AA`.
+//!
+//! ## Error messages
+//!
+//! <code>label(<var>pattern</var>, <var>str</var>)</code> - Matches *pattern*. If it fails
+//! without matching anything, the error message says "expected *str*". For example,
+//! if a line doesn't start with `inc` or `dec`, `parser!(lines(label({"inc", "dec"} " " i64, "an
+//! instruction")))` reports `expected an instruction` rather than `expected "dec"`. Any parser
+//! also has a [`label`][Parser::label] method that does the same thing.
+//!
 //! ## Custom conversion
 //!
 //! <code>... <var>name1</var>:<var>pattern1</var> ... => <var>expr</var></code> - On successfully
@@ -204,6 +315,40 @@
 //! The *name*s are in scope only for the following *expr* in the same set of matching parentheses
 //! or braces.
 //!
+//! <code>... <var>name1</var>:<var>pattern1</var> ... =>? <var>expr</var></code> - Like `=>`, but
+//! *expr* produces a `Result`. If it's `Err`, the match fails, and the error is reported as a
+//! `ParseError` located where the pattern matched. Use this for input that's well-formed but
+//! invalid, like coordinates out of range:
+//!
+//! ```
+//! # use aoc_parse::{parser, prelude::*};
+//! let p = parser!(lines(
+//!     x:u32 "," y:u32 =>? if x < 100 && y < 100 { Ok((x, y)) } else { Err("off the grid") }
+//! ));
+//! assert!(p
+//!     .parse("3,4\n99,150\n")
+//!     .unwrap_err()
+//!     .to_string()
+//!     .starts_with(r#"failed to convert "99,150": off the grid at line 2 column 1"#));
+//! ```
+//!
+//! Unlike `=>`, the *expr* after `=>?` always runs while matching, so it can run even if the
+//! overall parse fails later. (A `=>` inside a repeat, like `lines(...)` or `*`, can also run early:
+//! when the repeated pattern can match only one way, repeats convert each match as they find it.)
+//!
+//! <code>... <var>name1</var>:<var>pattern1</var> ... =>> <var>expr</var></code> - Match the
+//! patterns, then use their values to decide how to parse the text that follows: *expr* is a
+//! parser, and it's used to match the rest. The value is the value of that parser. For example,
+//! for a count followed by that many items:
+//!
+//! ```
+//! # use aoc_parse::{parser, prelude::*};
+//! let p = parser!(n:usize ": " =>> repeat_sep_n(u32, " ", n));
+//! assert_eq!(p.parse("3: 10 20 30").unwrap(), vec![10, 20, 30]);
+//! ```
+//!
+//! Like `=>?`, *expr* runs while matching. See [`then()`][prelude::then].
+//!
 //! ## Alternatives
 //!
 //! <code>{<var>pattern1</var>, <var>pattern2</var>, ...}</code> - Matches any one of the
@@ -300,6 +445,10 @@
 //!
 //! Note: Left-recursive grammars don't work, as usual for PEG parsers.
 //!
+//! A parser with rules is a [`RuleSetParser`]. If all its patterns can be shared across threads,
+//! so can it; and to avoid rebuilding it every time, it can be [boxed][Parser::boxed] and stored
+//! in a `static` using `LazyLock`.
+//!
 //! ## Lines and sections
 //!
 //! <code>line(<var>pattern</var>)</code> - Matches a single line of text that matches *pattern*,
@@ -329,6 +478,16 @@
 //! );
 //! ```
 //!
+//! <code>lines_lenient(<var>pattern</var>)</code> - Matches all remaining lines, but instead of
+//! failing on a line that doesn't match *pattern*, skips it and keeps going. Produces a pair
+//! `(values, errors)`: a `Vec` of values from the lines that matched, and a `Vec<ParseError>` with
+//! one error for each line that didn't. Useful for checking hand-edited test inputs.
+//!
+//! To process lines one at a time without collecting them into a `Vec`, use
+//! [`Parser::parse_iter_lines`]. For very large inputs, [`Parser::parse_lines_from`] reads lines
+//! from a `BufRead` one at a time and parses each one with a pattern, producing an iterator of
+//! results.
+//!
 //! <code>section(<var>pattern</var>)</code> - Matches zero or more nonblank lines, followed by
 //! either a blank line or the end of input. The nonblank lines must match *pattern*. For example,
 //! `section(lines(u64))` matches a section that's a list of numbers, one per line.
@@ -340,6 +499,12 @@
 //! <code>sections(<var>pattern</var>)</code> - Matches any number of sections matching *pattern*.
 //! Equivalent to <code>section(<var>pattern</var>)*</code>.
 //!
+//! <code>par_lines(<var>pattern</var>)</code>, <code>par_sections(<var>pattern</var>)</code> -
+//! Like `lines` and `sections`, but parse each line or section on a separate thread, using
+//! [rayon](https://docs.rs/rayon). These produce the same values and the same errors, and can
+//! be much faster for very large inputs. They require the `rayon` feature, and *pattern* can't
+//! refer to rules defined outside the call.
+//!
 //! ## Collections
 //!
 //! <code>hash_set(<var>pattern</var>)</code>, <code>hash_map(<var>pattern</var>)</code>,
@@ -379,6 +544,13 @@
 //! );
 //! ```
 //!
+//! ## Storing parsers
+//!
+//! Every parser has a different type, and the types can be long. To put parsers in a struct or a
+//! table, or return them from functions, call [`.boxed()`][Parser::boxed] to get a
+//! [`BoxedParser<T>`][BoxedParser], whose type depends only on the output type `T`. Boxed parsers
+//! are cheap to clone. `Box`, `Rc`, and `Arc` pointers to parsers are also parsers.
+//!
 //! ----
 //!
 //! Bringing it all together to parse a complex example:
@@ -415,21 +587,35 @@
 #![deny(missing_docs)]
 
 mod context;
+mod describe;
 mod error;
+mod line_index;
 #[doc(hidden)]
 pub mod macros;
 mod parsers;
+mod read;
 #[cfg(test)]
 mod testing;
+mod trace;
 mod traits;
 mod types;
 mod util;
 
 pub use context::{ParseContext, Reported};
+pub use describe::Describe;
 pub use error::ParseError;
 use error::Result;
+pub use parsers::{
+    BoxedParser, Case, EnumParser, EnumSpellings, Local, RuleSetParser, Shared, Sharing, StrSlice,
+};
+pub use read::{IterLines, ParseLines};
+pub use trace::Trace;
 pub use traits::{ParseIter, Parser};
 
+/// The crate that provides the types produced by `rational`, `decimal`, and
+/// friends.
+pub use num_rational;
+
 /// A giant sack of toys and goodies to import along with `parser!`.
 ///
 /// The `parser!()` macro will work fine without this, so you can explicitly
@@ -444,16 +630,32 @@ pub mod prelude {
 
     pub use crate::util::aoc_parse;
 
+    pub use crate::parsers::{any_byte, byte_digit, byte_line, byte_of};
+
+    pub use crate::parsers::{
+        ascii_alnum, ascii_alpha, ascii_lower, ascii_upper, char_class, char_where, none_of, punct,
+        whitespace,
+    };
+
+    pub use crate::parsers::{
+        alnum, alpha, any_char, big_decimal, big_int, big_int_bin, big_int_hex, big_rational,
+        big_uint, big_uint_bin, big_uint_hex, bool, btree_map, btree_set, char_of, decimal, digit,
+        digit_bin, digit_hex, digit_radix, digits_from, exact, f32, f64, grouped_i64, grouped_u64,
+        hash_map, hash_set, i128, i128_bin, i128_hex, i128_oct, i16, i16_bin, i16_hex, i16_oct,
+        i32, i32_bin, i32_hex, i32_oct, i64, i64_bin, i64_hex, i64_oct, i8, i8_bin, i8_hex, i8_oct,
+        int_auto, int_lenient, int_radix, int_underscored, isize, isize_bin, isize_hex, isize_oct,
+        lower, rational, u128, u128_bin, u128_hex, u128_oct, u16, u16_bin, u16_hex, u16_oct, u32,
+        u32_bin, u32_hex, u32_oct, u64, u64_bin, u64_hex, u64_oct, u8, u8_bin, u8_hex, u8_oct,
+        upper, usize, usize_bin, usize_hex, usize_oct, vec_deque,
+    };
+
     pub use crate::parsers::{
-        alnum, alpha, any_char, big_int, big_int_bin, big_int_hex, big_uint, big_uint_bin,
-        big_uint_hex, bool, btree_map, btree_set, char_of, digit, digit_bin, digit_hex, f32, f64,
-        hash_map, hash_set, i128, i128_bin, i128_hex, i16, i16_bin, i16_hex, i32, i32_bin, i32_hex,
-        i64, i64_bin, i64_hex, i8, i8_bin, i8_hex, isize, isize_bin, isize_hex, lower, u128,
-        u128_bin, u128_hex, u16, u16_bin, u16_hex, u32, u32_bin, u32_hex, u64, u64_bin, u64_hex,
-        u8, u8_bin, u8_hex, upper, usize, usize_bin, usize_hex, vec_deque,
+        label, line, lines, lines_lenient, nocase, one_of_str, one_of_str_map, repeat_n,
+        repeat_sep, repeat_sep_n, section, sections, then,
     };
 
-    pub use crate::parsers::{line, lines, repeat_sep, section, sections};
+    #[cfg(feature = "rayon")]
+    pub use crate::parsers::{par_lines, par_sections};
 
     /// Parse using `parser`, but instead of converting the matched text to a
     /// Rust value, simply return it as a `String`.
@@ -464,4 +666,24 @@ pub mod prelude {
     pub fn string<P: Parser>(parser: P) -> crate::parsers::StringParser<P> {
         crate::parsers::StringParser { parser }
     }
+
+    /// Parse using `parser`, but instead of converting the matched text to a
+    /// Rust value, return it as a [`StrSlice`][crate::StrSlice].
+    ///
+    /// This is like [`string()`], but doesn't allocate a new `String` for
+    /// each match. A `StrSlice` derefs to `str` and can be used as a map key.
+    ///
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use aoc_parse::{parser, prelude::*};
+    /// let p = parser!(hash_map(lines(str_slice(alpha+) " -> " u32)));
+    /// let map = p.parse("abc -> 1\nxy -> 2\n").unwrap();
+    /// assert_eq!(map["xy"], 2);
+    /// ```
+    ///
+    /// (A plain `&str` borrowed from the input isn't possible here, because a
+    /// parser's `Output` type can't depend on the lifetime of the input.)
+    pub fn str_slice<P: Parser>(parser: P) -> crate::parsers::StrSliceParser<P> {
+        crate::parsers::StrSliceParser { parser }
+    }
 }
@@ -0,0 +1,100 @@
+//! Precomputed positions of line starts and blank lines, so that `line()` and
+//! `section()` can check where they are, `section()` can find where each
+//! section ends, and errors can report line numbers, all without rescanning
+//! the input.
+//!
+//! `line()` doesn't use this to find the end of a line: `str::find` on a
+//! single line is already faster than a binary search.
+
+use std::sync::Arc;
+
+/// The positions of all the line starts and section breaks in an input string.
+#[derive(Debug)]
+pub(crate) struct LineIndex {
+    /// Offset of the start of every line: 0, and every offset just after a
+    /// `'\n'`. Shared with errors, which use it to find line numbers.
+    line_starts: Arc<[usize]>,
+
+    /// Offset of every `'\n'` that is immediately followed by another `'\n'`;
+    /// that is, every place where a section ends.
+    blank_lines: Vec<usize>,
+}
+
+impl LineIndex {
+    pub(crate) fn new(input: &str) -> Self {
+        let line_starts: Arc<[usize]> = std::iter::once(0)
+            .chain(
+                input
+                    .bytes()
+                    .enumerate()
+                    .filter(|&(_, b)| b == b'\n')
+                    .map(|(i, _)| i + 1),
+            )
+            .collect();
+        // Two line starts in a row, other than the start of the input, mean
+        // two newlines in a row.
+        let blank_lines = line_starts
+            .windows(2)
+            .filter(|pair| pair[0] != 0 && pair[1] == pair[0] + 1)
+            .map(|pair| pair[0] - 1)
+            .collect();
+        LineIndex {
+            line_starts,
+            blank_lines,
+        }
+    }
+
+    /// The offsets of the starts of all lines.
+    pub(crate) fn line_starts(&self) -> Arc<[usize]> {
+        self.line_starts.clone()
+    }
+
+    /// True if `offset` is at the start of a line.
+    pub(crate) fn is_line_start(&self, offset: usize) -> bool {
+        self.line_starts.binary_search(&offset).is_ok()
+    }
+
+    /// The offset of the first `"\n\n"` that lies entirely within
+    /// `start..end`, if any.
+    pub(crate) fn next_blank_line(&self, start: usize, end: usize) -> Option<usize> {
+        first_in(&self.blank_lines, start, end.saturating_sub(1))
+    }
+}
+
+/// The first element of the sorted slice `offsets` that is in `start..end`.
+fn first_in(offsets: &[usize], start: usize, end: usize) -> Option<usize> {
+    let i = offsets.partition_point(|&offset| offset < start);
+    offsets.get(i).copied().filter(|&offset| offset < end)
+}
+
+/// The index of the line containing `offset`, counting from 0, given the
+/// offsets of the starts of all lines.
+pub(crate) fn line_containing(line_starts: &[usize], offset: usize) -> usize {
+    line_starts
+        .partition_point(|&start| start <= offset)
+        .saturating_sub(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_index() {
+        let index = LineIndex::new("ab\n\ncd\nef\n\n");
+        assert_eq!(index.next_blank_line(0, 11), Some(2));
+        assert_eq!(index.next_blank_line(3, 11), Some(9));
+        // The second newline of the pair has to be in range too.
+        assert_eq!(index.next_blank_line(3, 10), None);
+
+        assert_eq!(&index.line_starts()[..], &[0, 3, 4, 7, 10, 11]);
+        assert!(index.is_line_start(0));
+        assert!(index.is_line_start(4));
+        assert!(!index.is_line_start(5));
+        assert_eq!(line_containing(&index.line_starts(), 0), 0);
+        assert_eq!(line_containing(&index.line_starts(), 2), 0);
+        assert_eq!(line_containing(&index.line_starts(), 3), 1);
+        assert_eq!(line_containing(&index.line_starts(), 8), 3);
+        assert_eq!(line_containing(&index.line_starts(), 11), 5);
+    }
+}
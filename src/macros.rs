@@ -68,8 +68,8 @@
 //! ```
 
 pub use crate::parsers::{
-    alt, empty, lines, map, opt, pair, plus, sequence, single_value, star, RuleParser,
-    RuleSetBuilder,
+    alt, empty, lines, map, opt, pair, plus, sequence, single_value, star, then, try_map,
+    RuleParser, RuleSetBuilder,
 };
 
 /// Macro that creates a parser for a given pattern.
@@ -83,6 +83,8 @@ pub use crate::parsers::{
 ///
 /// expr ::= seq
 ///   | seq "=>" rust_expr      -- custom conversion
+///   | seq "=>?" rust_expr     -- fallible custom conversion, producing a Result
+///   | seq "=>>" rust_expr     -- rust_expr is a parser for the text that follows
 ///
 /// seq ::= lterm
 ///   | seq lterm               -- concatenated subpatterns
@@ -100,6 +102,7 @@ pub use crate::parsers::{
 ///   | ident                   -- named parser (when not followed by `(`)
 ///   | literal                 -- exact char or string
 ///   | "{" expr,* "}"          -- one-of syntax
+///   | "[" rust_expr,* "]"     -- Rust array, e.g. for `one_of_str(["a", "b"])`
 ///
 /// ident ::= a Rust identifier
 /// expr ::= a Rust expression
@@ -112,6 +115,87 @@ macro_rules! parser {
     ($($pattern:tt)*) => { $crate::aoc_parse_helper!( $( $pattern )* ) }
 }
 
+/// Define an enum that can be parsed by the names of its variants.
+///
+/// This defines the enum as written, plus a constant of the same name that's
+/// a parser for it, so the enum's name can be used in patterns:
+///
+/// ```
+/// # use aoc_parse::{enum_parser, parser, prelude::*};
+/// enum_parser! {
+///     #[parse(rename_all = "lowercase")]
+///     #[derive(Debug, Clone, Copy, PartialEq)]
+///     enum Direction { North, South, East, West }
+/// }
+///
+/// let p = parser!(lines(Direction " " u32));
+/// assert_eq!(
+///     p.parse("north 3\nwest 10\n").unwrap(),
+///     vec![(Direction::North, 3), (Direction::West, 10)],
+/// );
+/// ```
+///
+/// The enum must implement `Clone`, and its variants can't have fields.
+///
+/// The optional `#[parse(rename_all = "...")]` attribute says how variant
+/// names are spelled in the input. It must come first. The options are
+/// `"lowercase"`, `"UPPERCASE"`, `"snake_case"`, `"SCREAMING_SNAKE_CASE"`,
+/// `"kebab-case"`, and `"words"`, which is lowercase words separated by
+/// spaces, so that `TurnOn` matches `turn on`. Without the attribute, names
+/// are spelled as written.
+///
+/// If one variant's name is a prefix of another's, the longer one is tried
+/// first. See [`EnumParser`][crate::EnumParser] to build this kind of parser
+/// without the macro.
+#[macro_export]
+macro_rules! enum_parser {
+    (
+        #[parse(rename_all = $case:tt)]
+        $($rest:tt)*
+    ) => {
+        $crate::enum_parser!(@enum ($crate::enum_parser!(@case $case)) $($rest)*);
+    };
+
+    (@case "lowercase") => { $crate::Case::Lower };
+    (@case "UPPERCASE") => { $crate::Case::Upper };
+    (@case "snake_case") => { $crate::Case::Snake };
+    (@case "SCREAMING_SNAKE_CASE") => { $crate::Case::ScreamingSnake };
+    (@case "kebab-case") => { $crate::Case::Kebab };
+    (@case "words") => { $crate::Case::Words };
+    (@case $other:literal) => {
+        ::core::compile_error!(::core::concat!("unsupported rename_all option: ", $other))
+    };
+
+    (
+        @enum ($case:expr)
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $( $(#[$vmeta:meta])* $variant:ident ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis enum $name {
+            $( $(#[$vmeta])* $variant , )*
+        }
+
+        #[doc = ::core::concat!("Parser for [`", ::core::stringify!($name), "`].")]
+        #[allow(non_upper_case_globals)]
+        $vis const $name: $crate::EnumParser<$name> = {
+            static SPELLINGS: $crate::EnumSpellings = $crate::EnumSpellings::new();
+            $crate::EnumParser::new(
+                ::core::stringify!($name),
+                &[ $( (::core::stringify!($variant), $name::$variant) , )* ],
+                $case,
+                &SPELLINGS,
+            )
+        };
+    };
+
+    ( $($rest:tt)* ) => {
+        $crate::enum_parser!(@enum ($crate::Case::AsWritten) $($rest)*);
+    };
+}
+
 #[macro_export]
 #[doc(hidden)]
 macro_rules! aoc_parse_helper {
@@ -132,6 +216,24 @@ macro_rules! aoc_parse_helper {
     // BUG: Because of the simplistic way this macro-parses the input, it
     // doesn't reject some bad syntax like `foo?(x)` or `foo??` or `foo++`.
 
+    // Fallible mapper, `expr ::= label =>? rust_expr`. This has to come first,
+    // because `? rust_expr` would be a hard error in the `=>` rule below.
+    (@seq [ => ? $mapper:expr ] [ $($stack:tt)* ] [ $($pats:tt ,)* ]) => {
+        $crate::macros::try_map(
+            $crate::aoc_parse_helper!(@reverse_map [ $($stack)* ] []),
+            | ( $crate::aoc_parse_helper!(@reverse_pats [ $($pats ,)* ] []) ) | $mapper ,
+        )
+    };
+
+    // Data-dependent pattern, `expr ::= label =>> rust_expr`. Like `=>?`, this
+    // has to come before the `=>` rule.
+    (@seq [ => > $next:expr ] [ $($stack:tt)* ] [ $($pats:tt ,)* ]) => {
+        $crate::macros::then(
+            $crate::aoc_parse_helper!(@reverse_map [ $($stack)* ] []),
+            | ( $crate::aoc_parse_helper!(@reverse_pats [ $($pats ,)* ] []) ) | $next ,
+        )
+    };
+
     // Mapper at the end of a pattern that is not labeled, `expr ::= label => rust_expr`.
     (@seq [ => $mapper:expr ] [ $($stack:tt)* ] [ $($pats:tt ,)* ]) => {
         $crate::macros::map(
@@ -342,6 +444,9 @@ macro_rules! aoc_parse_helper {
             $crate::aoc_parse_helper!(@seq [ $( $nested )* ] [ ] [ ])
         )
     };
+    (@prim [ $($array:tt)* ]) => {
+        [ $( $array )* ]
+    };
     (@prim { $($nested:tt)* }) => {
         $crate::aoc_parse_helper!(@list [ $( $nested )* ] [ ] [ ])
     };
@@ -451,10 +556,10 @@ macro_rules! aoc_parse_helper {
         {
             let mut builder = $crate::macros::RuleSetBuilder::new();
             $(
-                let $name : $crate::macros::RuleParser<$output_ty> = builder.new_rule();
+                let $name : $crate::macros::RuleParser<$output_ty> = builder.new_rule(stringify!($name));
             )*
             $(
-                builder.assign_parser_for_rule(
+                let builder = builder.assign_parser_for_rule(
                     &$name,
                     $crate::parser!(@seq [ $( $rule_pat )* ] [] [])
                 );
@@ -515,6 +620,12 @@ macro_rules! aoc_parse_helper {
         ::core::compile_error!(stringify!(unrecognized syntax @ $($tail)*))
     };
 
+    // A rule set. Its RawOutput is already a singleton tuple, so don't wrap
+    // it; that way the type is a `RuleSetParser`.
+    ( rule $( $tail:tt )* ) => {
+        $crate::aoc_parse_helper!(@split_rules [ rule $($tail)* ] [ ] [ ])
+    };
+
     // Hand anything else off to the @split_rules submacro.
     ( $( $tail:tt )* ) => {
         $crate::macros::single_value(
@@ -1,45 +1,73 @@
+mod bytes;
 mod chars;
 mod collections;
 mod dynamic;
 mod either;
 mod empty;
+mod enums;
 mod exact;
+mod int_format;
+mod keywords;
+mod label;
 mod lines;
 mod map;
+mod nocase;
+#[cfg(feature = "rayon")]
+mod parallel;
 mod primitive;
 mod regex;
 mod repeat;
 mod rule_set;
 mod sequence;
 mod string;
+mod then;
 
-pub use self::regex::RegexParser;
-pub use chars::{alnum, alpha, any_char, char_of, digit, digit_bin, digit_hex, lower, upper};
+pub use bytes::{any_byte, byte_digit, byte_line, byte_of};
+pub use chars::{
+    alnum, alpha, any_char, ascii_alnum, ascii_alpha, ascii_lower, ascii_upper, char_class,
+    char_of, char_where, digit, digit_bin, digit_hex, digit_radix, lower, none_of, punct, upper,
+    whitespace,
+};
 pub use collections::{btree_map, btree_set, hash_map, hash_set, vec_deque};
-pub use either::{alt, either, opt, AltParser, Either, EitherParser};
+pub use dynamic::{BoxedParser, Local, Shared, Sharing};
+pub use either::{alt, opt};
 pub use empty::{empty, EmptyParser};
-pub use lines::{line, lines, section, sections, LineParser, SectionParser};
-pub use map::{map, single_value, skip, MapParser};
+pub use enums::{Case, EnumParser, EnumSpellings};
+pub use exact::exact;
+pub use int_format::{grouped_i64, grouped_u64, int_auto, int_lenient, int_underscored};
+pub use keywords::{one_of_str, one_of_str_map};
+pub use label::{label, LabelParser};
+pub use lines::{line, lines, lines_lenient, section, sections};
+pub(crate) use lines::{match_fully, Line, Region};
+pub use map::{map, single_value, try_map, MapParser};
+pub use nocase::nocase;
+#[cfg(feature = "rayon")]
+pub use parallel::{par_lines, par_sections};
 pub use primitive::{
-    big_int, big_int_bin, big_int_hex, big_uint, big_uint_bin, big_uint_hex, bool, f32, f64, i128,
-    i128_bin, i128_hex, i16, i16_bin, i16_hex, i32, i32_bin, i32_hex, i64, i64_bin, i64_hex, i8,
-    i8_bin, i8_hex, isize, isize_bin, isize_hex, u128, u128_bin, u128_hex, u16, u16_bin, u16_hex,
-    u32, u32_bin, u32_hex, u64, u64_bin, u64_hex, u8, u8_bin, u8_hex, usize, usize_bin, usize_hex,
-    BasicParseIter,
+    big_decimal, big_int, big_int_bin, big_int_hex, big_rational, big_uint, big_uint_bin,
+    big_uint_hex, bool, decimal, digits_from, f32, f64, i128, i128_bin, i128_hex, i128_oct, i16,
+    i16_bin, i16_hex, i16_oct, i32, i32_bin, i32_hex, i32_oct, i64, i64_bin, i64_hex, i64_oct, i8,
+    i8_bin, i8_hex, i8_oct, int_radix, isize, isize_bin, isize_hex, isize_oct, rational, u128,
+    u128_bin, u128_hex, u128_oct, u16, u16_bin, u16_hex, u16_oct, u32, u32_bin, u32_hex, u32_oct,
+    u64, u64_bin, u64_hex, u64_oct, u8, u8_bin, u8_hex, u8_oct, usize, usize_bin, usize_hex,
+    usize_oct, BasicParseIter,
 };
-pub use repeat::{plus, repeat, repeat_sep, star, RepeatParser};
+pub use repeat::{plus, repeat_n, repeat_sep, repeat_sep_n, star, RepeatParser};
 pub use rule_set::{RuleParser, RuleSetBuilder, RuleSetParser};
-pub use sequence::{pair, sequence, SequenceParser};
-pub use string::StringParser;
+pub use sequence::{pair, sequence};
+pub use string::{StrSlice, StrSliceParser, StringParser};
+pub use then::then;
 
 // --- Wrappers
 
 #[cfg(test)]
 mod tests {
+    use super::either::{either, Either};
     use super::*;
     use crate::testing::*;
 
     #[test]
+    #[allow(clippy::needless_borrows_for_generic_args)]
     fn test_parse() {
         let p = empty();
         assert_parse_eq(p, "", ());
@@ -0,0 +1,198 @@
+//! Parsers that produce bytes rather than characters, for inputs that are
+//! really ASCII grids.
+//!
+//! These still run on `str` input, like all other parsers. Except for
+//! `byte_line`, they only match ASCII characters, so a match never ends in
+//! the middle of a multibyte character.
+
+use std::fmt;
+
+use crate::{
+    parsers::{
+        lines::{Line, Region},
+        BasicParseIter, MapParser,
+    },
+    ParseContext, Parser, Reported, Result,
+};
+
+/// The type of the `any_byte` and `byte_digit` parsers.
+#[derive(Clone, Copy)]
+pub struct ByteParser {
+    name: &'static str,
+    noun: &'static str,
+    predicate: fn(u8) -> bool,
+}
+
+/// The type of parser returned by [`byte_of()`].
+#[derive(Clone, Copy)]
+pub struct ByteOfParser<B = &'static [u8]> {
+    options: B,
+}
+
+/// The type of the `byte_line` parser.
+#[derive(Clone, Copy)]
+pub struct ByteLineParser;
+
+impl Parser for ByteParser {
+    type Output = u8;
+    type RawOutput = (u8,);
+    type Iter<'parse> = BasicParseIter<u8>;
+
+    fn parse_iter<'parse>(
+        &'parse self,
+        context: &mut ParseContext<'parse>,
+        start: usize,
+    ) -> Result<Self::Iter<'parse>, Reported> {
+        match context.source().as_bytes().get(start) {
+            Some(&b) if b.is_ascii() && (self.predicate)(b) => Ok(BasicParseIter {
+                end: start + 1,
+                value: b,
+            }),
+            _ => Err(context.error_expected(start, self.noun)),
+        }
+    }
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name)
+    }
+}
+
+impl<B> Parser for ByteOfParser<B>
+where
+    B: AsRef<[u8]>,
+{
+    type Output = usize;
+    type RawOutput = (usize,);
+    type Iter<'parse>
+        = BasicParseIter<usize>
+    where
+        B: 'parse;
+
+    fn parse_iter<'parse>(
+        &'parse self,
+        context: &mut ParseContext<'parse>,
+        start: usize,
+    ) -> Result<Self::Iter<'parse>, Reported> {
+        let options = self.options.as_ref();
+        if let Some(&b) = context.source().as_bytes().get(start) {
+            if b.is_ascii() {
+                if let Some(i) = options.iter().position(|&x| x == b) {
+                    return Ok(BasicParseIter {
+                        end: start + 1,
+                        value: i,
+                    });
+                }
+            }
+        }
+        Err(context.error_expected(
+            start,
+            &format!("one of {:?}", String::from_utf8_lossy(options)),
+        ))
+    }
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let options = self.options.as_ref();
+        write!(f, "byte_of(b{:?})", String::from_utf8_lossy(options))
+    }
+}
+
+impl Parser for ByteLineParser {
+    type Output = Vec<u8>;
+    type RawOutput = (Vec<u8>,);
+    type Iter<'parse> = BasicParseIter<Vec<u8>>;
+
+    fn parse_iter<'parse>(
+        &'parse self,
+        context: &mut ParseContext<'parse>,
+        start: usize,
+    ) -> Result<Self::Iter<'parse>, Reported> {
+        Line::check_at_start(context, start)?;
+        let (inner_end, outer_end) = Line::find_end(context, start)?;
+        Ok(BasicParseIter {
+            end: outer_end,
+            value: context.source().as_bytes()[start..inner_end].to_vec(),
+        })
+    }
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("byte_line")
+    }
+}
+
+/// Matches any ASCII character and produces it as a `u8`.
+#[allow(non_upper_case_globals)]
+pub const any_byte: ByteParser = ByteParser {
+    name: "any_byte",
+    noun: "an ASCII character",
+    predicate: |_| true,
+};
+
+/// Matches an ASCII decimal digit `'0'`-`'9'` and converts it to its value
+/// `0`-`9`, as a `u8`.
+#[allow(non_upper_case_globals)]
+pub const byte_digit: MapParser<ByteParser, fn(u8) -> u8> = MapParser {
+    inner: ByteParser {
+        name: "byte_digit",
+        noun: "a decimal digit",
+        predicate: |b| b.is_ascii_digit(),
+    },
+    mapper: |b| b - b'0',
+};
+
+/// Matches a single line and produces its contents, without the newline, as a
+/// `Vec<u8>`. `byte_line*` parses a grid of bytes.
+#[allow(non_upper_case_globals)]
+pub const byte_line: ByteLineParser = ByteLineParser;
+
+/// Make a parser that matches any single byte in `options` and produces the
+/// index of that byte in the list, so that `byte_of(b".#")` produces `0` or
+/// `1`. Only ASCII bytes ever match.
+///
+/// Like `char_of`, `options` can be built at run time, as a `Vec<u8>` for
+/// example.
+pub fn byte_of<B: AsRef<[u8]>>(options: B) -> ByteOfParser<B> {
+    ByteOfParser { options }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::*;
+
+    #[test]
+    fn test_bytes() {
+        assert_parse_eq(any_byte, "#", b'#');
+        assert_parse_error(any_byte, "é", "expected an ASCII character");
+        assert_parse_eq(plus(byte_digit), "309", vec![3, 0, 9]);
+        assert_parse_eq(byte_of(b".#"), "#", 1);
+        assert_parse_error(byte_of(b".#"), "x", "expected one of \".#\"");
+        let options: Vec<u8> = b"ab".to_vec();
+        assert_parse_eq(byte_of(options), "b", 1);
+
+        let grid = star(byte_line);
+        assert_parse_eq(grid, "#.\n.#\n", vec![b"#.".to_vec(), b".#".to_vec()]);
+    }
+
+    #[test]
+    fn test_parse_bytes() {
+        let p = star(byte_line);
+        assert_eq!(
+            p.parse_bytes(b"ab\ncd").unwrap(),
+            vec![b"ab".to_vec(), b"cd".to_vec()]
+        );
+
+        let err = p.parse_bytes(b"ab\nc\xffd\n").unwrap_err();
+        assert_eq!(err.location, 4);
+        assert!(err
+            .to_string()
+            .starts_with("input is not valid UTF-8 at line 2 column 2"));
+
+        // Even byte parsers that would accept any byte don't get to see input
+        // that isn't UTF-8.
+        let err = star(byte_of([b'.', 0xff]))
+            .parse_bytes(b"..\xff")
+            .unwrap_err();
+        assert_eq!(err.location, 2);
+        assert!(err.to_string().starts_with("input is not valid UTF-8"));
+    }
+}
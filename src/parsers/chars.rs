@@ -1,3 +1,7 @@
+use std::{fmt, marker::PhantomData};
+
+use num_traits::PrimInt;
+
 use crate::{
     parsers::{BasicParseIter, MapParser},
     ParseContext, ParseIter, Parser, Reported, Result,
@@ -5,6 +9,7 @@ use crate::{
 
 #[derive(Clone, Copy)]
 pub struct CharParser {
+    name: &'static str,
     noun: &'static str,
     predicate: fn(char) -> bool,
 }
@@ -16,8 +21,8 @@ pub struct CharParseIter {
 
 /// The type of parser returned by [`char_of()`].
 #[derive(Clone, Copy)]
-pub struct CharOfParser {
-    options: &'static str,
+pub struct CharOfParser<S = &'static str> {
+    options: S,
 }
 
 impl Parser for CharParser {
@@ -38,10 +43,17 @@ impl Parser for CharParser {
             _ => Err(context.error_expected(start, self.noun)),
         }
     }
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name)
+    }
 }
 
 impl<'parse> ParseIter<'parse> for CharParseIter {
     type RawOutput = (char,);
+    fn is_deterministic() -> bool {
+        true
+    }
     fn match_end(&self) -> usize {
         self.end
     }
@@ -53,19 +65,71 @@ impl<'parse> ParseIter<'parse> for CharParseIter {
     }
 }
 
-impl Parser for CharOfParser {
+/// The type of parser returned by [`char_class()`] and [`none_of()`].
+#[derive(Clone, Debug)]
+pub struct CharClassParser {
+    /// Inclusive ranges of characters.
+    ranges: Vec<(char, char)>,
+    /// True if the parser matches characters *not* in `ranges`.
+    negated: bool,
+    /// The function that created the parser, for messages.
+    name: &'static str,
+    /// Description of the characters that match, for messages.
+    noun: String,
+    /// The string the parser was created from, for messages.
+    spec: String,
+}
+
+impl CharClassParser {
+    fn contains(&self, c: char) -> bool {
+        self.ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi) != self.negated
+    }
+}
+
+impl Parser for CharClassParser {
+    type Output = char;
+    type RawOutput = (char,);
+    type Iter<'parse> = CharParseIter;
+
+    fn parse_iter<'parse>(
+        &'parse self,
+        context: &mut ParseContext<'parse>,
+        start: usize,
+    ) -> Result<Self::Iter<'parse>, Reported> {
+        match context.source()[start..].chars().next() {
+            Some(c) if self.contains(c) => Ok(CharParseIter {
+                c,
+                end: start + c.len_utf8(),
+            }),
+            _ => Err(context.error_expected(start, &self.noun)),
+        }
+    }
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}({:?})", self.name, self.spec)
+    }
+}
+
+impl<S> Parser for CharOfParser<S>
+where
+    S: AsRef<str>,
+{
     type Output = usize;
     type RawOutput = (usize,);
-    type Iter<'parse> = BasicParseIter<usize>;
+    type Iter<'parse>
+        = BasicParseIter<usize>
+    where
+        S: 'parse;
     fn parse_iter<'parse>(
         &'parse self,
         context: &mut ParseContext<'parse>,
         start: usize,
     ) -> Result<Self::Iter<'parse>, Reported> {
+        let options = self.options.as_ref();
         if let Some(c) = context.source()[start..].chars().next() {
-            // Note: `self.options.find(c)` would give the wrong answer here: we
-            // want the number of characters before `c`, not bytes.
-            for (i, x) in self.options.chars().enumerate() {
+            // Note: `options.find(c)` would give the wrong answer here: we want
+            // the number of characters before `c`, not bytes.
+            for (i, x) in options.chars().enumerate() {
                 if c == x {
                     return Ok(BasicParseIter {
                         value: i,
@@ -74,14 +138,69 @@ impl Parser for CharOfParser {
                 }
             }
         }
-        Err(context.error_expected(start, &format!("one of {:?}", self.options)))
+        Err(context.error_expected(start, &format!("one of {options:?}")))
+    }
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "char_of({:?})", self.options.as_ref())
+    }
+}
+
+/// The type of parser returned by [`digit_radix()`].
+pub struct DigitRadixParser<T> {
+    radix: u32,
+    phantom: PhantomData<fn() -> T>,
+}
+
+// Manual Clone and Copy impls because `#[derive]` would require `T: Copy`.
+impl<T> Clone for DigitRadixParser<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for DigitRadixParser<T> {}
+
+impl<T> Parser for DigitRadixParser<T>
+where
+    T: PrimInt,
+{
+    type Output = T;
+    type RawOutput = (T,);
+    type Iter<'parse>
+        = BasicParseIter<T>
+    where
+        T: 'parse;
+
+    fn parse_iter<'parse>(
+        &'parse self,
+        context: &mut ParseContext<'parse>,
+        start: usize,
+    ) -> Result<Self::Iter<'parse>, Reported> {
+        let c = context.source()[start..].chars().next();
+        match c.and_then(|c| Some((c, c.to_digit(self.radix)?))) {
+            // Every integer type can hold a digit, which is at most 35.
+            Some((c, d)) => Ok(BasicParseIter {
+                value: T::from(d).unwrap(),
+                end: start + c.len_utf8(),
+            }),
+            None => {
+                let noun = format!("a base-{} digit", self.radix);
+                Err(context.error_expected(start, &noun))
+            }
+        }
+    }
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "digit_radix({})", self.radix)
     }
 }
 
 /// Matches any alphabetic character (see [`char::is_alphabetic`]). Returns a `char`.
 #[allow(non_upper_case_globals)]
 pub const alpha: CharParser = CharParser {
-    noun: "letter",
+    name: "alpha",
+    noun: "a letter",
     predicate: char::is_alphabetic,
 };
 
@@ -89,27 +208,81 @@ pub const alpha: CharParser = CharParser {
 /// [`char::is_alphanumeric`]). Returns a `char`.
 #[allow(non_upper_case_globals)]
 pub const alnum: CharParser = CharParser {
-    noun: "letter or digit",
+    name: "alnum",
+    noun: "a letter or digit",
     predicate: char::is_alphanumeric,
 };
 
 /// Matches any uppercase letter (see [`char::is_uppercase`]). Returns a `char`.
 #[allow(non_upper_case_globals)]
 pub const upper: CharParser = CharParser {
-    noun: "uppercase letter",
+    name: "upper",
+    noun: "an uppercase letter",
     predicate: char::is_uppercase,
 };
 
 /// Matches any lowercase letter (see [`char::is_lowercase`]). Returns a `char`.
 #[allow(non_upper_case_globals)]
 pub const lower: CharParser = CharParser {
-    noun: "lowercase letter",
+    name: "lower",
+    noun: "a lowercase letter",
     predicate: char::is_lowercase,
 };
 
+/// Matches any whitespace character (see [`char::is_whitespace`]),
+/// including newlines. Returns a `char`.
+#[allow(non_upper_case_globals)]
+pub const whitespace: CharParser = CharParser {
+    name: "whitespace",
+    noun: "a whitespace character",
+    predicate: char::is_whitespace,
+};
+
+/// Matches any ASCII punctuation character, like `.` or `#` (see
+/// [`char::is_ascii_punctuation`]). Returns a `char`.
+#[allow(non_upper_case_globals)]
+pub const punct: CharParser = CharParser {
+    name: "punct",
+    noun: "a punctuation character",
+    predicate: |c| c.is_ascii_punctuation(),
+};
+
+/// Matches any ASCII letter `'A'`-`'Z'` or `'a'`-`'z'`. Returns a `char`.
+#[allow(non_upper_case_globals)]
+pub const ascii_alpha: CharParser = CharParser {
+    name: "ascii_alpha",
+    noun: "an ASCII letter",
+    predicate: |c| c.is_ascii_alphabetic(),
+};
+
+/// Matches any ASCII letter or decimal digit. Returns a `char`.
+#[allow(non_upper_case_globals)]
+pub const ascii_alnum: CharParser = CharParser {
+    name: "ascii_alnum",
+    noun: "an ASCII letter or digit",
+    predicate: |c| c.is_ascii_alphanumeric(),
+};
+
+/// Matches any ASCII uppercase letter `'A'`-`'Z'`. Returns a `char`.
+#[allow(non_upper_case_globals)]
+pub const ascii_upper: CharParser = CharParser {
+    name: "ascii_upper",
+    noun: "an ASCII uppercase letter",
+    predicate: |c| c.is_ascii_uppercase(),
+};
+
+/// Matches any ASCII lowercase letter `'a'`-`'z'`. Returns a `char`.
+#[allow(non_upper_case_globals)]
+pub const ascii_lower: CharParser = CharParser {
+    name: "ascii_lower",
+    noun: "an ASCII lowercase letter",
+    predicate: |c| c.is_ascii_lowercase(),
+};
+
 /// Matches any Unicode character. Returns a `char`.
 #[allow(non_upper_case_globals)]
 pub const any_char: CharParser = CharParser {
+    name: "any_char",
     noun: "any character",
     predicate: |_| true,
 };
@@ -119,7 +292,8 @@ pub const any_char: CharParser = CharParser {
 #[allow(non_upper_case_globals)]
 pub const digit: MapParser<CharParser, fn(char) -> usize> = MapParser {
     inner: CharParser {
-        noun: "decimal digit",
+        name: "digit",
+        noun: "a decimal digit",
         predicate: |c| c.is_ascii_digit(),
     },
     mapper: |c| c.to_digit(10).unwrap() as usize,
@@ -130,7 +304,8 @@ pub const digit: MapParser<CharParser, fn(char) -> usize> = MapParser {
 #[allow(non_upper_case_globals)]
 pub const digit_bin: MapParser<CharParser, fn(char) -> usize> = MapParser {
     inner: CharParser {
-        noun: "hexadecimal digit",
+        name: "digit_bin",
+        noun: "a binary digit",
         predicate: |c| c.is_digit(2),
     },
     mapper: |c| c.to_digit(2).unwrap() as usize,
@@ -141,7 +316,8 @@ pub const digit_bin: MapParser<CharParser, fn(char) -> usize> = MapParser {
 #[allow(non_upper_case_globals, clippy::is_digit_ascii_radix)]
 pub const digit_hex: MapParser<CharParser, fn(char) -> usize> = MapParser {
     inner: CharParser {
-        noun: "hexadecimal digit",
+        name: "digit_hex",
+        noun: "a hexadecimal digit",
         predicate: |c| c.is_digit(16),
     },
     mapper: |c| c.to_digit(16).unwrap() as usize,
@@ -150,15 +326,256 @@ pub const digit_hex: MapParser<CharParser, fn(char) -> usize> = MapParser {
 /// Make a parser that matches any single character in `options` and produces
 /// the index of that character in the list, so that `char_of("ABCD")` produces
 /// a number in `0..4`.
-pub fn char_of(options: &'static str) -> CharOfParser {
+///
+/// `options` can be a `String` built at run time, for example from an earlier
+/// part of the puzzle input.
+pub fn char_of<S: AsRef<str>>(options: S) -> CharOfParser<S> {
     CharOfParser { options }
 }
 
+/// Make a parser that matches a single digit in the given `radix`, which
+/// must be in the range 2 to 36, and converts it to its value. Digits above 9
+/// are the letters `a` to `z`, in either case. Produces a value of type `T`,
+/// which can be any primitive integer type.
+///
+/// ```
+/// # use aoc_parse::{parser, prelude::*};
+/// let octal = digit_radix::<u8>(8);
+/// let p = parser!(lines(octal+));
+/// assert_eq!(p.parse("170\n").unwrap(), vec![vec![1, 7, 0]]);
+/// assert!(p.parse("18\n").is_err());
+/// ```
+///
+/// # Panics
+///
+/// If `radix` is not in the range 2 to 36.
+pub fn digit_radix<T: PrimInt>(radix: u32) -> DigitRadixParser<T> {
+    assert!(
+        (2..=36).contains(&radix),
+        "digit_radix: radix must be in the range 2 to 36, not {radix}"
+    );
+    DigitRadixParser {
+        radix,
+        phantom: PhantomData,
+    }
+}
+
+/// Make a parser that matches any character for which `predicate` returns
+/// true. Returns a `char`. `noun` describes the characters that match, for
+/// error messages.
+///
+/// ```
+/// # use aoc_parse::{parser, prelude::*};
+/// let vowel = char_where("a vowel", |c| "aeiou".contains(c));
+/// let p = parser!(string(vowel+));
+/// assert_eq!(p.parse("eau").unwrap(), "eau");
+/// assert!(p.parse("why").unwrap_err().to_string().starts_with("expected a vowel"));
+/// ```
+///
+/// This is a `const fn`, so the result can be a constant, like the built-in
+/// character parsers.
+pub const fn char_where(noun: &'static str, predicate: fn(char) -> bool) -> CharParser {
+    CharParser {
+        name: "char_where",
+        noun,
+        predicate,
+    }
+}
+
+/// Make a parser that matches any single character in the character class
+/// `class`, written as in a regular expression but without the square
+/// brackets: `char_class("a-zA-Z_")` matches a letter or underscore. Returns
+/// a `char`.
+///
+/// `class` is a list of characters and ranges like `a-z`. Other than that:
+///
+/// -   A `^` at the beginning negates the class: `char_class("^0-9")`
+///     matches any character that isn't a digit, including a newline.
+///
+/// -   A `-` at the beginning or end of the class (after any `^`) matches a
+///     hyphen.
+///
+/// -   A backslash escapes the next character. `\\`, `\-`, `\^`, `\[`, and
+///     `\]` match that character; `\n`, `\t`, and `\r` match a newline, tab,
+///     and carriage return.
+///
+/// Other regular expression syntax, like `\d` or `[:alpha:]`, is not
+/// supported.
+///
+/// ```
+/// # use aoc_parse::{parser, prelude::*};
+/// let p = parser!(string(char_class(r"^\]\\")+));
+/// assert_eq!(p.parse("a-z").unwrap(), "a-z");
+/// assert!(p.parse("a]z").is_err());
+/// ```
+///
+/// # Panics
+///
+/// If a range is backwards, like `"z-a"`, or an escape isn't one of the ones
+/// listed above.
+pub fn char_class(class: &str) -> CharClassParser {
+    let (negated, body) = match class.strip_prefix('^') {
+        Some(rest) if !rest.is_empty() => (true, rest),
+        _ => (false, class),
+    };
+
+    // Each character, with escapes resolved, and whether it was escaped.
+    let mut chars = vec![];
+    let mut iter = body.chars();
+    while let Some(c) = iter.next() {
+        if c == '\\' {
+            let c = match iter.next() {
+                Some('n') => '\n',
+                Some('t') => '\t',
+                Some('r') => '\r',
+                Some(c @ ('\\' | '-' | '^' | '[' | ']')) => c,
+                Some(c) => panic!("unsupported escape \\{c} in char_class({class:?})"),
+                None => panic!("char_class({class:?}) ends with a backslash"),
+            };
+            chars.push((c, true));
+        } else {
+            chars.push((c, false));
+        }
+    }
+
+    let mut ranges = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        if i + 2 < chars.len() && chars[i + 1] == ('-', false) {
+            let (lo, hi) = (chars[i].0, chars[i + 2].0);
+            assert!(lo <= hi, "invalid range {lo}-{hi} in char_class({class:?})");
+            ranges.push((lo, hi));
+            i += 3;
+        } else {
+            ranges.push((chars[i].0, chars[i].0));
+            i += 1;
+        }
+    }
+    CharClassParser {
+        ranges,
+        negated,
+        name: "char_class",
+        noun: format!("a character in [{class}]"),
+        spec: class.to_string(),
+    }
+}
+
+/// Make a parser that matches any single character that is *not* in
+/// `chars`, including a newline unless it's in `chars`. Returns a `char`.
+///
+/// For example, `parser!(string(none_of(",\n")+))` matches a field of a
+/// comma-separated line.
+pub fn none_of(chars: &str) -> CharClassParser {
+    CharClassParser {
+        ranges: chars.chars().map(|c| (c, c)).collect(),
+        negated: true,
+        name: "none_of",
+        noun: format!("any character except {chars:?}"),
+        spec: chars.to_string(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::testing::*;
 
+    #[test]
+    fn test_char_classes() {
+        let p = char_class("a-zA-Z_");
+        assert_parse_eq(&p, "q", 'q');
+        assert_parse_eq(&p, "Z", 'Z');
+        assert_parse_eq(&p, "_", '_');
+        assert_parse_error(
+            &p,
+            "-",
+            "expected a character in [a-zA-Z_] at line 1 column 1",
+        );
+        assert_eq!(p.describe().to_string(), r#"char_class("a-zA-Z_")"#);
+
+        let p = char_class("-0-9é");
+        assert_parse_eq(&p, "-", '-');
+        assert_parse_eq(&p, "5", '5');
+        assert_parse_eq(&p, "é", 'é');
+        assert_no_parse(&p, "e");
+
+        // Escapes.
+        let p = char_class(r"a\-z");
+        assert_parse_eq(&p, "-", '-');
+        assert_no_parse(&p, "b");
+        assert_no_parse(&p, "\\");
+        let p = char_class(r"\-+");
+        assert_parse_eq(&p, "-", '-');
+        assert_parse_eq(&p, "+", '+');
+        assert_no_parse(&p, ",");
+        let p = char_class(r"[\]\\\^\n");
+        assert_parse_eq(&p, "]", ']');
+        assert_parse_eq(&p, "\\", '\\');
+        assert_parse_eq(&p, "^", '^');
+        assert_parse_eq(&p, "\n", '\n');
+        assert_no_parse(&p, "n");
+
+        // Negation.
+        let p = char_class("^0-9");
+        assert_parse_eq(&p, "x", 'x');
+        assert_parse_eq(&p, "\n", '\n');
+        assert_parse_error(&p, "7", "expected a character in [^0-9] at line 1 column 1");
+        assert_eq!(p.describe().to_string(), r#"char_class("^0-9")"#);
+        assert_parse_eq(char_class("a^"), "^", '^');
+        assert_parse_eq(char_class("^"), "^", '^');
+        assert_parse_eq(char_class(r"\^a"), "^", '^');
+
+        let p = none_of(",\n");
+        assert_parse_eq(&p, "x", 'x');
+        assert_parse_eq(&p, "\r", '\r');
+        assert_parse_error(&p, ",", r#"expected any character except ",\n" at"#);
+        assert_parse_error(&p, "", "expected any character except");
+        assert_eq!(p.describe().to_string(), r#"none_of(",\n")"#);
+
+        let p = char_where("a vowel", |c| "aeiou".contains(c));
+        assert_parse_eq(p, "e", 'e');
+        assert_parse_error(p, "y", "expected a vowel at");
+    }
+
+    #[test]
+    fn test_digit_radix() {
+        let p = digit_radix::<u8>(8);
+        assert_parse_eq(p, "7", 7);
+        assert_parse_error(p, "8", "expected a base-8 digit at");
+        assert_eq!(p.describe().to_string(), "digit_radix(8)");
+        let p = digit_radix::<i64>(36);
+        assert_parse_eq(p, "Z", 35);
+        assert_parse_eq(p, "a", 10);
+        assert_parse_error(p, "", "expected a base-36 digit at end");
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid range z-a")]
+    fn test_char_class_backwards_range() {
+        char_class("z-a");
+    }
+
+    #[test]
+    #[should_panic(expected = r"unsupported escape \d")]
+    fn test_char_class_bad_escape() {
+        char_class(r"\d");
+    }
+
+    #[test]
+    fn test_ascii_classes() {
+        assert_parse_eq(whitespace, "\t", '\t');
+        assert_parse_eq(whitespace, "\u{3000}", '\u{3000}');
+        assert_parse_eq(punct, "#", '#');
+        assert_parse_error(punct, "a", "expected a punctuation character");
+        assert_parse_eq(ascii_alpha, "k", 'k');
+        assert_parse_error(ascii_alpha, "é", "expected an ASCII letter");
+        assert_parse_eq(ascii_alnum, "7", '7');
+        assert_parse_eq(ascii_upper, "K", 'K');
+        assert_no_parse(ascii_upper, "k");
+        assert_parse_eq(ascii_lower, "k", 'k');
+        assert_no_parse(ascii_lower, "Ω");
+    }
+
     #[test]
     fn test_char_of() {
         assert_parse_eq(char_of("<=>"), "<", 0);
@@ -173,6 +590,10 @@ mod tests {
         assert_parse_eq(char_of("DCBA"), "C", 1);
         assert_parse_error(char_of("DCBA"), "DC", "at line 1 column 2");
 
+        let options = "xyz".to_string();
+        assert_parse_eq(char_of(options.clone()), "z", 2);
+        assert_parse_eq(char_of(&options), "y", 1);
+
         // Nonsense parser, nonsense error message; but check that the behavior
         // is correct.
         assert_parse_error(char_of(""), "", "expected one of \"\"");
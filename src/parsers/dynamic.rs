@@ -1,27 +1,78 @@
 //! A type that behaves like `Box<dyn Parser<Output=T>>` (even though `Parser`
 //! itself has other associated types that would make this impossible).
 
+use std::{fmt, sync::Arc};
+
 use crate::{
     parsers::{map::SingleValueParser, single_value},
     ParseIter, Parser, Reported, Result,
 };
 
-trait MyDynParserTrait<Out> {
+// `pub` only so that it can appear in `Sharing`. Nothing outside this module
+// can name it.
+pub trait MyDynParserTrait<Out> {
     fn parse_iter<'parse>(
         &'parse self,
         context: &mut crate::ParseContext<'parse>,
         start: usize,
-    ) -> Result<DynParseIter<'parse, Out>, Reported>;
+    ) -> Result<BoxedParseIter<'parse, Out>, Reported>;
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result;
 }
 
-/// Any parser that produces a value of type Out.
+/// Any parser that produces a value of type `T`, with the actual parser type
+/// erased. Returned by [`Parser::boxed`].
+///
+/// Parser types can get very long, and every `parser!` call has a different
+/// type. A `BoxedParser` can be stored in a struct or a table, or returned
+/// from a function, without naming the real type:
+///
+/// ```
+/// # use aoc_parse::{parser, prelude::*, BoxedParser};
+/// fn number_list(sep: &'static str) -> BoxedParser<'static, Vec<i64>> {
+///     parser!(repeat_sep(i64, sep)).boxed()
+/// }
+///
+/// let parsers = [number_list(","), number_list(" "), parser!(n:i64 => vec![n]).boxed()];
+/// assert_eq!(parsers[1].parse("1 -2 3").unwrap(), vec![1, -2, 3]);
+/// ```
+///
+/// Cloning a `BoxedParser` is cheap: clones share the same parser, using an
+/// `Arc`.
 ///
-/// Unlike most parsers, this is not necessarily `Copy` or `Clone`.
-pub struct DynParser<'parser, Out> {
-    inner: Box<dyn MyDynParserTrait<Out> + 'parser>,
+/// A `BoxedParser` is `Send` and `Sync`, so the parser being boxed must be
+/// too. To box a parser that isn't, such as one whose `=>` expression
+/// captures an `Rc`, use [`BoxedParser::new_local`], which makes a
+/// `BoxedParser<T, Local>` that can only be used on one thread.
+pub struct BoxedParser<'parser, T, S: Sharing = Shared> {
+    inner: Arc<S::DynParser<'parser, T>>,
+}
+
+/// Marker for a [`BoxedParser`] that is `Send` and `Sync`. This is the
+/// default.
+pub enum Shared {}
+
+/// Marker for a [`BoxedParser`] that holds a parser that isn't `Send` or
+/// `Sync`, and so can't be shared across threads.
+pub enum Local {}
+
+/// Implemented by [`Shared`] and [`Local`]. Says what kind of trait object a
+/// boxed parser is stored as.
+pub trait Sharing: 'static {
+    #[doc(hidden)]
+    type DynParser<'parser, T>: ?Sized + MyDynParserTrait<T>;
 }
 
-pub struct DynParseIter<'parse, T> {
+impl Sharing for Shared {
+    type DynParser<'parser, T> = dyn MyDynParserTrait<T> + Send + Sync + 'parser;
+}
+
+impl Sharing for Local {
+    type DynParser<'parser, T> = dyn MyDynParserTrait<T> + 'parser;
+}
+
+/// The parse iterator type for [`BoxedParser`].
+pub struct BoxedParseIter<'parse, T> {
     inner: Box<dyn ParseIter<'parse, RawOutput = (T,)> + 'parse>,
 }
 
@@ -33,47 +84,69 @@ where
         &'parse self,
         context: &mut crate::ParseContext<'parse>,
         start: usize,
-    ) -> Result<DynParseIter<'parse, P::Output>, Reported> {
+    ) -> Result<BoxedParseIter<'parse, P::Output>, Reported> {
         let iter = <SingleValueParser<P> as Parser>::parse_iter(self, context, start)?;
-        Ok(DynParseIter {
+        Ok(BoxedParseIter {
             inner: Box::new(iter),
         })
     }
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        <SingleValueParser<P> as Parser>::fmt_pattern(self, f)
+    }
 }
 
-impl<'parser, Out> DynParser<'parser, Out> {
-    /// Wrap any parser in a `DynParser<'parser, T>`, parameterized only on the
-    /// output type (not Iter or RawOutput).
-    ///
-    /// This isn't exposed as a documented API for a couple of reasons.
-    ///
-    /// -   I'm not sure we can't do better; this type doesn't pass through `Copy`,
-    ///     `Clone`, `Send`, or `Sync`. It also hardcodes `Box`, so patterns like
-    ///     `Arc<dyn ...>` won't work. (Of course for the Advent of Code use case
-    ///     this doesn't matter too much.)
-    ///
-    /// -   This is meant as an implemention detail of rule-sets, a `parser!`
-    ///     feature. There are several other similar functions, and so far all of
-    ///     them are undocumented.
+impl<'parser, T> BoxedParser<'parser, T> {
+    /// Wrap any parser in a `BoxedParser<'parser, T>`, parameterized only on
+    /// the output type (not Iter or RawOutput). [`Parser::boxed`] calls
+    /// this.
+    pub fn new<P>(parser: P) -> Self
+    where
+        P: Parser<Output = T> + Send + Sync + 'parser,
+    {
+        BoxedParser {
+            inner: Arc::new(single_value(parser)),
+        }
+    }
+}
+
+impl<'parser, T> BoxedParser<'parser, T, Local> {
+    /// Like [`BoxedParser::new`], but for parsers that aren't `Send` or
+    /// `Sync`.
     ///
-    #[doc(hidden)]
-    #[allow(dead_code)]
-    pub(crate) fn new<P>(parser: P) -> Self
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use aoc_parse::{parser, prelude::*, BoxedParser};
+    /// let names = Rc::new(vec!["zero", "one"]);
+    /// let p = BoxedParser::new_local(parser!(i:usize => names[i]));
+    /// assert_eq!(p.parse("1").unwrap(), "one");
+    /// ```
+    pub fn new_local<P>(parser: P) -> Self
     where
-        P: Parser<Output = Out> + 'parser,
+        P: Parser<Output = T> + 'parser,
     {
-        DynParser {
-            inner: Box::new(single_value(parser)),
+        BoxedParser {
+            inner: Arc::new(single_value(parser)),
         }
     }
 }
 
-impl<'parser, Out> Parser for DynParser<'parser, Out> {
-    type Output = Out;
+// Manual Clone impl because `#[derive(Clone)]` would require `T: Clone`.
+impl<'parser, T, S: Sharing> Clone for BoxedParser<'parser, T, S> {
+    fn clone(&self) -> Self {
+        BoxedParser {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
 
-    type RawOutput = (Out,);
+impl<'parser, T, S: Sharing> Parser for BoxedParser<'parser, T, S> {
+    type Output = T;
+
+    type RawOutput = (T,);
 
-    type Iter<'parse> = DynParseIter<'parse, Out>
+    type Iter<'parse>
+        = BoxedParseIter<'parse, T>
     where
         Self: 'parse;
 
@@ -85,9 +158,13 @@ impl<'parser, Out> Parser for DynParser<'parser, Out> {
         let iter = self.inner.parse_iter(context, start)?;
         Ok(iter)
     }
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt_pattern(f)
+    }
 }
 
-impl<'parse, T> ParseIter<'parse> for DynParseIter<'parse, T> {
+impl<'parse, T> ParseIter<'parse> for BoxedParseIter<'parse, T> {
     type RawOutput = (T,);
 
     fn match_end(&self) -> usize {
@@ -111,13 +188,35 @@ mod tests {
 
     #[test]
     fn test_dynamic() {
-        let mut p = DynParser::new(u64);
-        assert_parse_eq(p, "1141413", 1141413);
+        let mut p = BoxedParser::new(u64);
+        assert_parse_eq(&p, "1141413", 1141413);
 
         // We assign another parser, with a completely different underlying
         // implementation type, to the same variable, because the wrapper has
         // the same type.
-        p = DynParser::new(map(i32, |x| x as u64));
-        assert_parse_eq(p, "-1", 0xffff_ffff_ffff_ffff_u64);
+        p = BoxedParser::new(map(i32, |x| x as u64));
+        assert_parse_eq(&p, "-1", 0xffff_ffff_ffff_ffff_u64);
+
+        // Clones share the parser.
+        let q = p.clone();
+        assert_parse_eq(q, "-2", 0xffff_ffff_ffff_fffe_u64);
+        assert_eq!(p.describe().to_string(), "i32");
+    }
+
+    #[test]
+    fn test_dynamic_send_sync() {
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+        // Even if the output type isn't.
+        let p = BoxedParser::new(map(u64, std::rc::Rc::new));
+        assert_send_sync(&p);
+    }
+
+    #[test]
+    fn test_dynamic_local() {
+        let offset = std::rc::Rc::new(10);
+        let p = BoxedParser::new_local(map(u64, move |x| x + *offset));
+        assert_parse_eq(&p, "5", 15);
+        assert_parse_eq(p.clone(), "6", 16);
     }
 }
@@ -1,7 +1,11 @@
 //! Alternation.
 
+use std::fmt;
+
 use crate::{
+    describe,
     parsers::{empty, map, EmptyParser, MapParser},
+    trace,
     types::ParserOutput,
     ParseContext, ParseIter, Parser, Reported, Result,
 };
@@ -35,7 +39,8 @@ where
 {
     type Output = Either<A::Output, B::Output>;
     type RawOutput = (Either<A::Output, B::Output>,);
-    type Iter<'parse> = EitherParseIter<'parse, A, B>
+    type Iter<'parse>
+        = EitherParseIter<'parse, A, B>
     where
         A: 'parse,
         B: 'parse;
@@ -45,9 +50,9 @@ where
         context: &mut ParseContext<'parse>,
         start: usize,
     ) -> Result<Self::Iter<'parse>, Reported> {
-        let iter = match self.left.parse_iter(context, start) {
+        let iter = match trace::parse_iter(&self.left, context, start) {
             Ok(iter) => Either::Left(iter),
-            Err(Reported) => Either::Right(self.right.parse_iter(context, start)?),
+            Err(Reported) => Either::Right(trace::parse_iter(&self.right, context, start)?),
         };
         Ok(EitherParseIter {
             start,
@@ -55,6 +60,27 @@ where
             iter,
         })
     }
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // `opt(p)` is `either(p, empty())`.
+        if self.right.is_empty_parser() {
+            describe::write_atom(f, &self.left)?;
+            return f.write_str("?");
+        }
+
+        // Nested alternations are flattened, `{a, b, c}` not `{a, {b, c}}`.
+        let braces = !describe::is_arm_position(f);
+        if braces {
+            f.write_str("{")?;
+        }
+        describe::write_arm(f, &self.left)?;
+        f.write_str(", ")?;
+        describe::write_arm(f, &self.right)?;
+        if braces {
+            f.write_str("}")?;
+        }
+        Ok(())
+    }
 }
 
 impl<'parse, A, B> ParseIter<'parse> for EitherParseIter<'parse, A, B>
@@ -72,15 +98,16 @@ where
     }
 
     fn backtrack(&mut self, context: &mut ParseContext<'parse>) -> Result<(), Reported> {
+        let parsers = self.parsers;
         match &mut self.iter {
             Either::Left(iter) => {
-                if iter.backtrack(context).is_ok() {
+                if trace::backtrack(&parsers.left, iter, context).is_ok() {
                     return Ok(());
                 }
-                self.iter = Either::Right(self.parsers.right.parse_iter(context, self.start)?);
+                self.iter = Either::Right(trace::parse_iter(&parsers.right, context, self.start)?);
                 Ok(())
             }
-            Either::Right(iter) => iter.backtrack(context),
+            Either::Right(iter) => trace::backtrack(&parsers.right, iter, context),
         }
     }
 
@@ -1,5 +1,7 @@
 //! Parser that successfully matches the empty string.
 
+use std::fmt;
+
 use crate::{ParseContext, ParseIter, Parser, Reported, Result};
 
 #[derive(Clone, Copy)]
@@ -17,6 +19,14 @@ impl Parser for EmptyParser {
     ) -> Result<EmptyParseIter, Reported> {
         Ok(EmptyParseIter { location: start })
     }
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("()")
+    }
+
+    fn is_empty_parser(&self) -> bool {
+        true
+    }
 }
 
 pub struct EmptyParseIter {
@@ -25,6 +35,9 @@ pub struct EmptyParseIter {
 
 impl<'parse> ParseIter<'parse> for EmptyParseIter {
     type RawOutput = ();
+    fn is_deterministic() -> bool {
+        true
+    }
     fn match_end(&self) -> usize {
         self.location
     }
@@ -0,0 +1,275 @@
+//! Parsing enum variants by name. See [`enum_parser!`][crate::enum_parser].
+
+use std::{fmt, sync::OnceLock};
+
+use crate::{parsers::keywords::Trie, ParseContext, ParseIter, Parser, Reported, Result};
+
+/// How the names of enum variants are spelled in the input. Used by
+/// [`EnumParser`].
+///
+/// Variant names are split into words at uppercase letters, so with
+/// `Case::Snake`, `TurnOn` is spelled `turn_on`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Case {
+    /// Exactly as in the Rust source, like `TurnOn`.
+    AsWritten,
+    /// All lowercase, like `turnon`.
+    Lower,
+    /// All uppercase, like `TURNON`.
+    Upper,
+    /// Lowercase words separated by underscores, like `turn_on`.
+    Snake,
+    /// Uppercase words separated by underscores, like `TURN_ON`.
+    ScreamingSnake,
+    /// Lowercase words separated by hyphens, like `turn-on`.
+    Kebab,
+    /// Lowercase words separated by spaces, like `turn on`.
+    Words,
+}
+
+impl Case {
+    /// Call `f` on each character of `name` spelled in this case. Stop early
+    /// if `f` returns false, and return false.
+    fn spell(self, name: &str, mut f: impl FnMut(char) -> bool) -> bool {
+        let separator = match self {
+            Case::Snake | Case::ScreamingSnake => Some('_'),
+            Case::Kebab => Some('-'),
+            Case::Words => Some(' '),
+            Case::AsWritten | Case::Lower | Case::Upper => None,
+        };
+        for (i, c) in name.char_indices() {
+            if i > 0 && c.is_uppercase() {
+                if let Some(sep) = separator {
+                    if !f(sep) {
+                        return false;
+                    }
+                }
+            }
+            let ok = match self {
+                Case::AsWritten => f(c),
+                Case::Upper | Case::ScreamingSnake => c.to_uppercase().all(&mut f),
+                Case::Lower | Case::Snake | Case::Kebab | Case::Words => {
+                    c.to_lowercase().all(&mut f)
+                }
+            };
+            if !ok {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Spell `name` in this case.
+    fn spelling(self, name: &str) -> String {
+        let mut s = String::new();
+        self.spell(name, |c| {
+            s.push(c);
+            true
+        });
+        s
+    }
+}
+
+/// A parser that matches the name of any variant of an enum `T`, producing
+/// that variant. Usually created by [`enum_parser!`][crate::enum_parser].
+pub struct EnumParser<T: 'static> {
+    name: &'static str,
+    variants: &'static [(&'static str, T)],
+    case: Case,
+    spellings: &'static EnumSpellings,
+}
+
+/// Where an [`EnumParser`] keeps the spellings of its variants, worked out
+/// the first time it's used. Each parser needs its own, in a `static`.
+pub struct EnumSpellings(OnceLock<Spellings>);
+
+struct Spellings {
+    /// Each variant's name, spelled as it appears in the input.
+    names: Vec<String>,
+    trie: Trie,
+    /// What to say in errors when nothing matches.
+    expected: String,
+}
+
+impl EnumSpellings {
+    /// Make empty storage, to be filled in the first time the parser runs.
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        EnumSpellings(OnceLock::new())
+    }
+}
+
+// Manual Clone and Copy impls because `#[derive]` would require `T: Copy`.
+impl<T> Clone for EnumParser<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for EnumParser<T> {}
+
+impl<T> EnumParser<T> {
+    /// Make a parser for the enum type named `name`, which has the given
+    /// variants, spelled in the input as `case` says.
+    ///
+    /// ```
+    /// # use aoc_parse::{parser, prelude::*, Case, EnumParser, EnumSpellings};
+    /// #[derive(Clone, Debug, PartialEq)]
+    /// enum Dir { Up, Down }
+    ///
+    /// static DIR_SPELLINGS: EnumSpellings = EnumSpellings::new();
+    /// const DIR: EnumParser<Dir> =
+    ///     EnumParser::new("Dir", &[("Up", Dir::Up), ("Down", Dir::Down)], Case::Lower, &DIR_SPELLINGS);
+    ///
+    /// let p = parser!(lines(DIR));
+    /// assert_eq!(p.parse("up\ndown\n").unwrap(), vec![Dir::Up, Dir::Down]);
+    /// ```
+    pub const fn new(
+        name: &'static str,
+        variants: &'static [(&'static str, T)],
+        case: Case,
+        spellings: &'static EnumSpellings,
+    ) -> Self {
+        EnumParser {
+            name,
+            variants,
+            case,
+            spellings,
+        }
+    }
+
+    fn spellings(&self) -> &'static Spellings {
+        self.spellings.0.get_or_init(|| {
+            let names: Vec<String> = self
+                .variants
+                .iter()
+                .map(|(name, _)| self.case.spelling(name))
+                .collect();
+            let trie = Trie::new(&names);
+            let quoted: Vec<String> = names.iter().map(|name| format!("{name:?}")).collect();
+            let expected = format!("one of {}", quoted.join(", "));
+            Spellings {
+                names,
+                trie,
+                expected,
+            }
+        })
+    }
+}
+
+pub struct EnumParseIter<T: 'static> {
+    start: usize,
+    variants: &'static [(&'static str, T)],
+    spellings: &'static Spellings,
+    /// Indexes of the variants that match, longest last.
+    matches: Vec<usize>,
+}
+
+impl<T> Parser for EnumParser<T>
+where
+    T: Clone,
+{
+    type Output = T;
+    type RawOutput = (T,);
+    type Iter<'parse> = EnumParseIter<T>;
+
+    fn parse_iter<'parse>(
+        &'parse self,
+        context: &mut ParseContext<'parse>,
+        start: usize,
+    ) -> Result<Self::Iter<'parse>, Reported> {
+        let spellings = self.spellings();
+        let matches = spellings.trie.prefixes_of(&context.source()[start..]);
+        if matches.is_empty() {
+            return Err(context.error_expected(start, &spellings.expected));
+        }
+        Ok(EnumParseIter {
+            start,
+            variants: self.variants,
+            spellings,
+            matches,
+        })
+    }
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name)
+    }
+}
+
+impl<'parse, T> ParseIter<'parse> for EnumParseIter<T>
+where
+    T: Clone,
+{
+    type RawOutput = (T,);
+
+    fn match_end(&self) -> usize {
+        self.start + self.spellings.names[*self.matches.last().unwrap()].len()
+    }
+
+    fn backtrack(&mut self, _context: &mut ParseContext<'parse>) -> Result<(), Reported> {
+        self.matches.pop();
+        if self.matches.is_empty() {
+            Err(Reported)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn convert(&self) -> (T,) {
+        (self.variants[*self.matches.last().unwrap()].1.clone(),)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::*;
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum Compass {
+        North,
+        NorthEast,
+        East,
+    }
+
+    const VARIANTS: &[(&str, Compass)] = &[
+        ("North", Compass::North),
+        ("NorthEast", Compass::NorthEast),
+        ("East", Compass::East),
+    ];
+
+    #[test]
+    fn test_case() {
+        let spellings: Vec<String> = [
+            Case::AsWritten,
+            Case::Lower,
+            Case::Upper,
+            Case::Snake,
+            Case::ScreamingSnake,
+            Case::Kebab,
+            Case::Words,
+        ]
+        .into_iter()
+        .map(|case| case.spelling("TurnOn"))
+        .collect();
+        assert_eq!(
+            spellings,
+            ["TurnOn", "turnon", "TURNON", "turn_on", "TURN_ON", "turn-on", "turn on"]
+        );
+    }
+
+    #[test]
+    fn test_enum_parser() {
+        static SPELLINGS: EnumSpellings = EnumSpellings::new();
+        let p = EnumParser::new("Compass", VARIANTS, Case::Kebab, &SPELLINGS);
+        assert_parse_eq(p, "north", Compass::North);
+        assert_parse_eq(p, "north-east", Compass::NorthEast);
+        assert_parse_eq(sequence(p, "-east"), "north-east", Compass::North);
+        assert_parse_error(
+            p,
+            "North",
+            r#"expected one of "north", "north-east", "east" at line 1 column 1"#,
+        );
+        assert_eq!(p.describe().to_string(), "Compass");
+    }
+}
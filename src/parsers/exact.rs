@@ -1,11 +1,31 @@
 //! Parser that matches a particular exact string.
 
+use std::fmt;
+
 use crate::{ParseContext, ParseIter, Parser, Reported, Result};
 
 pub struct ExactParseIter {
     end: usize,
 }
 
+/// If the source text at `start` matches `literal`, return the length of the
+/// match. Inside `nocase()`, case doesn't matter, so the length can differ
+/// from `literal.len()`.
+fn match_literal(context: &ParseContext<'_>, start: usize, literal: &str) -> Option<usize> {
+    let text = &context.source()[start..];
+    if !context.ignore_case() {
+        return text.starts_with(literal).then_some(literal.len());
+    }
+    let mut chars = text.chars();
+    for expected in literal.chars() {
+        let actual = chars.next()?;
+        if actual != expected && !actual.to_lowercase().eq(expected.to_lowercase()) {
+            return None;
+        }
+    }
+    Some(text.len() - chars.as_str().len())
+}
+
 impl Parser for str {
     type Output = ();
     type RawOutput = ();
@@ -16,14 +36,45 @@ impl Parser for str {
         context: &mut ParseContext<'parse>,
         start: usize,
     ) -> Result<ExactParseIter, Reported> {
-        if context.source()[start..].starts_with(self) {
-            Ok(ExactParseIter {
-                end: start + self.len(),
-            })
-        } else {
-            Err(context.error_expected(start, &format!("{self:?}")))
+        match match_literal(context, start, self) {
+            Some(len) => Ok(ExactParseIter { end: start + len }),
+            None => Err(context.error_expected(start, &format!("{self:?}"))),
         }
     }
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+/// The type of parser returned by [`exact()`].
+#[derive(Clone, Copy, Debug)]
+pub struct ExactParser<S> {
+    text: S,
+}
+
+impl<S> Parser for ExactParser<S>
+where
+    S: AsRef<str>,
+{
+    type Output = ();
+    type RawOutput = ();
+    type Iter<'parse>
+        = ExactParseIter
+    where
+        S: 'parse;
+
+    fn parse_iter<'parse>(
+        &'parse self,
+        context: &mut ParseContext<'parse>,
+        start: usize,
+    ) -> Result<ExactParseIter, Reported> {
+        self.text.as_ref().parse_iter(context, start)
+    }
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.text.as_ref().fmt_pattern(f)
+    }
 }
 
 impl Parser for char {
@@ -36,18 +87,29 @@ impl Parser for char {
         context: &mut ParseContext<'parse>,
         start: usize,
     ) -> Result<ExactParseIter, Reported> {
-        if context.source()[start..].starts_with(*self) {
-            Ok(ExactParseIter {
-                end: start + self.len_utf8(),
-            })
-        } else {
-            Err(context.error_expected(start, &format!("{self:?}")))
+        match match_literal(context, start, self.encode_utf8(&mut [0; 4])) {
+            Some(len) => Ok(ExactParseIter { end: start + len }),
+            None => Err(context.error_expected(start, &format!("{self:?}"))),
         }
     }
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+/// Make a parser that matches exactly the given text, which can be a
+/// `String` built at run time. The parser owns the string, so unlike a
+/// borrowed `&str`, it can outlive the data it was built from.
+pub fn exact<S: AsRef<str>>(text: S) -> ExactParser<S> {
+    ExactParser { text }
 }
 
 impl<'parse> ParseIter<'parse> for ExactParseIter {
     type RawOutput = ();
+    fn is_deterministic() -> bool {
+        true
+    }
     fn match_end(&self) -> usize {
         self.end
     }
@@ -59,6 +121,7 @@ impl<'parse> ParseIter<'parse> for ExactParseIter {
 
 #[cfg(test)]
 mod tests {
+    use super::exact;
     use crate::testing::*;
 
     #[test]
@@ -71,10 +134,20 @@ mod tests {
         assert_parse_eq(&p, "hello world!", ());
     }
 
+    #[test]
+    fn test_owned_string() {
+        let word = "light".to_string() + " red";
+        let p = exact(word.clone());
+        drop(word);
+        assert_parse_eq(sequence(&p, " bags"), "light red bags", ());
+        assert_parse_error(&p, "dark red", r#"expected "light red" at"#);
+        assert_eq!(p.describe().to_string(), r#""light red""#);
+    }
+
     #[test]
     fn test_exact_char_errors() {
         let p = '\n';
-        assert_parse_error(&p, "q", r#"expected '\n' at"#);
-        assert_parse_error(&p, "", r#"expected '\n' at end"#);
+        assert_parse_error(p, "q", r#"expected '\n' at"#);
+        assert_parse_error(p, "", r#"expected '\n' at end"#);
     }
 }
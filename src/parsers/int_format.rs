@@ -0,0 +1,364 @@
+//! Integers written the way people and programs write them, with base
+//! prefixes like `0x`, underscores, thousands separators, or loosely written
+//! minus signs.
+
+use std::{fmt, marker::PhantomData};
+
+use num_traits::PrimInt;
+
+use crate::{describe, parsers::BasicParseIter, ParseContext, Parser, Reported, Result};
+
+#[derive(Clone, Copy, Debug)]
+enum Format {
+    /// Plain decimal digits.
+    Decimal,
+    /// Optional `0x`, `0o` or `0b` prefix; underscores between digits.
+    Auto,
+    /// Decimal digits with underscores between them.
+    Underscored,
+    /// Decimal digits in groups of three, separated by the given character.
+    Grouped(char),
+}
+
+/// The type of parser returned by [`int_auto()`], [`int_underscored()`],
+/// [`grouped_u64()`], [`grouped_i64()`] and [`int_lenient()`].
+pub struct IntFormatParser<T> {
+    name: &'static str,
+    format: Format,
+    signed: bool,
+    /// Accept `\u{2212}` as a minus sign, and spaces after the sign.
+    lenient: bool,
+    phantom: PhantomData<fn() -> T>,
+}
+
+// Manual Clone and Copy impls because `#[derive]` would require `T: Copy`.
+impl<T> Clone for IntFormatParser<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for IntFormatParser<T> {}
+
+/// Count the ASCII digits in `radix` at the start of `bytes`.
+fn count_digits(bytes: &[u8], radix: u32) -> usize {
+    bytes
+        .iter()
+        .take_while(|&&b| char::from(b).is_digit(radix))
+        .count()
+}
+
+impl<T> IntFormatParser<T> {
+    /// Match the digits at the start of `text`, which follow the sign. Returns
+    /// the radix, the length of the match, and the digits without prefix or
+    /// separators. Returns `None` if there are no digits.
+    fn match_digits(&self, text: &str) -> Option<(u32, usize, String)> {
+        let bytes = text.as_bytes();
+        let (radix, prefix_len) = match self.format {
+            Format::Auto => match bytes {
+                [b'0', b'x' | b'X', b, ..] if b.is_ascii_hexdigit() => (16, 2),
+                [b'0', b'o' | b'O', b'0'..=b'7', ..] => (8, 2),
+                [b'0', b'b' | b'B', b'0' | b'1', ..] => (2, 2),
+                _ => (10, 0),
+            },
+            Format::Decimal | Format::Underscored | Format::Grouped(_) => (10, 0),
+        };
+
+        let mut end = prefix_len;
+        let first = count_digits(&bytes[end..], radix);
+        if first == 0 {
+            return None;
+        }
+        end += first;
+        let mut digits = text[prefix_len..end].to_string();
+
+        match self.format {
+            Format::Decimal => {}
+            Format::Auto | Format::Underscored => {
+                // Underscores are allowed only between digits, so `1_` only
+                // matches `1`.
+                loop {
+                    let underscores = bytes[end..].iter().take_while(|&&b| b == b'_').count();
+                    let n = count_digits(&bytes[end + underscores..], radix);
+                    if underscores == 0 || n == 0 {
+                        break;
+                    }
+                    digits += &text[end + underscores..end + underscores + n];
+                    end += underscores + n;
+                }
+            }
+            Format::Grouped(sep) => {
+                // After a first group of 1 to 3 digits, every group is exactly
+                // 3 digits. A number with no separators is fine too.
+                if first <= 3 {
+                    let sep_len = sep.len_utf8();
+                    while text[end..].starts_with(sep)
+                        && count_digits(&bytes[end + sep_len..], radix) == 3
+                    {
+                        digits += &text[end + sep_len..end + sep_len + 3];
+                        end += sep_len + 3;
+                    }
+                }
+            }
+        }
+        Some((radix, end, digits))
+    }
+}
+
+impl<T> Parser for IntFormatParser<T>
+where
+    T: PrimInt,
+    T::FromStrRadixErr: fmt::Display,
+{
+    type Output = T;
+    type RawOutput = (T,);
+    type Iter<'parse>
+        = BasicParseIter<T>
+    where
+        T: 'parse;
+
+    fn parse_iter<'parse>(
+        &'parse self,
+        context: &mut ParseContext<'parse>,
+        start: usize,
+    ) -> Result<Self::Iter<'parse>, Reported> {
+        let source = context.source();
+        let (sign, mut digits_start) = match source[start..].chars().next() {
+            Some('-') if self.signed => ("-", start + 1),
+            Some('+') if self.signed => ("+", start + 1),
+            Some('\u{2212}') if self.signed && self.lenient => ("-", start + '\u{2212}'.len_utf8()),
+            _ => ("", start),
+        };
+        if self.lenient && !sign.is_empty() {
+            digits_start +=
+                source[digits_start..].len() - source[digits_start..].trim_start_matches(' ').len();
+        }
+        let Some((radix, len, digits)) = self.match_digits(&source[digits_start..]) else {
+            let noun = if self.signed {
+                "an integer"
+            } else {
+                "an unsigned integer"
+            };
+            return Err(context.error_expected(start, noun));
+        };
+        let end = digits_start + len;
+        match T::from_str_radix(&(sign.to_string() + &digits), radix) {
+            Ok(value) => Ok(BasicParseIter { end, value }),
+            Err(err) => Err(context.error_from_str_failed(
+                start,
+                end,
+                describe::type_name::<T>(),
+                format!("{err}"),
+            )),
+        }
+    }
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.format {
+            Format::Grouped(sep) => write!(f, "{}({sep:?})", self.name),
+            Format::Decimal | Format::Auto | Format::Underscored => write!(f, "{}()", self.name),
+        }
+    }
+}
+
+fn is_signed<T: PrimInt>() -> bool {
+    T::min_value() < T::zero()
+}
+
+/// Make a parser for integers written with an optional base prefix: `0x` for
+/// hexadecimal, `0o` for octal, or `0b` for binary. Without a prefix, the
+/// number is decimal. As in Rust source code, underscores can appear between
+/// digits. Produces a value of type `T`, which can be any primitive integer
+/// type; if `T` is signed, a leading `+` or `-` sign is accepted.
+///
+/// ```
+/// # use aoc_parse::{parser, prelude::*};
+/// let int = int_auto::<i64>();
+/// let p = parser!(repeat_sep(int, " "));
+/// assert_eq!(p.parse("0x1F -0b101 0o17 1_000").unwrap(), vec![31, -5, 15, 1000]);
+/// ```
+pub fn int_auto<T: PrimInt>() -> IntFormatParser<T> {
+    IntFormatParser {
+        name: "int_auto",
+        format: Format::Auto,
+        signed: is_signed::<T>(),
+        lenient: false,
+        phantom: PhantomData,
+    }
+}
+
+/// Make a parser for decimal integers that may have underscores between
+/// digits, like `1_000_000`. Produces a value of type `T`, which can be any
+/// primitive integer type; if `T` is signed, a leading `+` or `-` sign is
+/// accepted.
+///
+/// ```
+/// # use aoc_parse::{parser, prelude::*};
+/// let int = int_underscored::<u32>();
+/// let p = parser!(lines(int));
+/// assert_eq!(p.parse("1_000\n12\n").unwrap(), vec![1000, 12]);
+/// ```
+pub fn int_underscored<T: PrimInt>() -> IntFormatParser<T> {
+    IntFormatParser {
+        name: "int_underscored",
+        format: Format::Underscored,
+        signed: is_signed::<T>(),
+        lenient: false,
+        phantom: PhantomData,
+    }
+}
+
+/// Make a parser for unsigned decimal integers with thousands separators,
+/// like `1,234,567` with `separator` `','`. Numbers without separators, like
+/// `1234567`, also match. Produces a `u64`.
+///
+/// Only well-formed groups are matched: after the first group of 1 to 3
+/// digits, each group must have exactly 3 digits. In `1,000,00`, only
+/// `1,000` matches.
+///
+/// ```
+/// # use aoc_parse::{parser, prelude::*};
+/// let p = parser!(lines("$" grouped_u64(',')));
+/// assert_eq!(p.parse("$1,250\n$3,000,000\n$75\n").unwrap(), vec![1250, 3000000, 75]);
+/// ```
+pub fn grouped_u64(separator: char) -> IntFormatParser<u64> {
+    IntFormatParser {
+        name: "grouped_u64",
+        format: Format::Grouped(separator),
+        signed: false,
+        lenient: false,
+        phantom: PhantomData,
+    }
+}
+
+/// Like [`grouped_u64()`], but a leading `+` or `-` sign is accepted, and the
+/// result is an `i64`.
+pub fn grouped_i64(separator: char) -> IntFormatParser<i64> {
+    IntFormatParser {
+        name: "grouped_i64",
+        format: Format::Grouped(separator),
+        signed: true,
+        lenient: false,
+        phantom: PhantomData,
+    }
+}
+
+/// Make a parser for decimal integers that tolerates signs written loosely:
+/// spaces between the sign and the digits, as in `- 5`, and the Unicode
+/// minus sign `−` (U+2212), often seen in text copied from web pages. These
+/// are normalized to a plain `-` before the number is converted to `T`, which
+/// can be any primitive integer type. If `T` is unsigned, no sign is accepted.
+///
+/// ```
+/// # use aoc_parse::{parser, prelude::*};
+/// let int = int_lenient::<i32>();
+/// let p = parser!(repeat_sep(int, ", "));
+/// assert_eq!(p.parse("- 5, \u{2212}12, + 3, 7").unwrap(), vec![-5, -12, 3, 7]);
+/// ```
+pub fn int_lenient<T: PrimInt>() -> IntFormatParser<T> {
+    IntFormatParser {
+        name: "int_lenient",
+        format: Format::Decimal,
+        signed: is_signed::<T>(),
+        lenient: true,
+        phantom: PhantomData,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::*;
+
+    #[test]
+    fn test_int_auto() {
+        let p = int_auto::<i32>();
+        assert_parse_eq(p, "0x1F", 31);
+        assert_parse_eq(p, "0XfF", 255);
+        assert_parse_eq(p, "-0b101", -5);
+        assert_parse_eq(p, "+0o17", 15);
+        assert_parse_eq(p, "017", 17);
+        assert_parse_eq(p, "0", 0);
+        assert_parse_eq(p, "0xff_ff", 0xffff);
+        assert_parse_eq(p, "-2_147_483_648", i32::MIN);
+        assert_parse_eq(sequence(p, "_"), "1_", 1);
+        assert_parse_eq(sequence(p, "xyz"), "0xyz", 0);
+        assert_parse_eq(sequence(p, "b2"), "0b2", 0);
+        assert_parse_error(p, "x", "expected an integer at line 1 column 1");
+        assert_parse_error(
+            p,
+            "0x8000_0000",
+            r#"failed to parse "0x8000_0000" as type i32: number too large"#,
+        );
+        assert_eq!(p.describe().to_string(), "int_auto()");
+
+        let p = int_auto::<u8>();
+        assert_parse_eq(p, "0xff", 255);
+        assert_no_parse(p, "-1");
+        assert_parse_error(p, "", "expected an unsigned integer at end");
+    }
+
+    #[test]
+    fn test_int_underscored() {
+        let p = int_underscored::<i64>();
+        assert_parse_eq(p, "1_000_000", 1_000_000);
+        assert_parse_eq(p, "-1__0", -10);
+        assert_parse_eq(sequence(p, "x10"), "0x10", 0);
+        assert_no_parse(p, "_1");
+        assert_eq!(p.describe().to_string(), "int_underscored()");
+    }
+
+    #[test]
+    fn test_int_lenient() {
+        let p = int_lenient::<i64>();
+        assert_parse_eq(p, "-5", -5);
+        assert_parse_eq(p, "-   5", -5);
+        assert_parse_eq(p, "+ 5", 5);
+        assert_parse_eq(p, "\u{2212}42", -42);
+        assert_parse_eq(p, "\u{2212} 42", -42);
+        assert_parse_eq(p, "17", 17);
+        assert_no_parse(p, "5_0");
+        assert_no_parse(p, "-\n5");
+        assert_no_parse(p, " 5");
+        assert_parse_error(p, "- x", "expected an integer at line 1 column 1");
+        assert_parse_error(
+            p,
+            "\u{2212} 9223372036854775809",
+            "number too small to fit in target type",
+        );
+        assert_eq!(p.describe().to_string(), "int_lenient()");
+
+        // Strict parsers don't accept either form.
+        assert_no_parse(int_auto::<i64>(), "- 5");
+        assert_no_parse(int_auto::<i64>(), "\u{2212}5");
+
+        let p = int_lenient::<u32>();
+        assert_parse_eq(p, "5", 5);
+        assert_no_parse(p, "\u{2212}5");
+    }
+
+    #[test]
+    fn test_grouped() {
+        let p = grouped_u64(',');
+        assert_parse_eq(p, "1,000", 1000);
+        assert_parse_eq(p, "12,345,678", 12345678);
+        assert_parse_eq(p, "999", 999);
+        assert_parse_eq(p, "1234567", 1234567);
+        assert_parse_eq(sequence(p, ",00"), "1,000,00", 1000);
+        assert_parse_eq(sequence(p, ",0000"), "1,0000", 1);
+        assert_parse_eq(sequence(p, ",000"), "1234,000", 1234);
+        assert_no_parse(p, "-1");
+        assert_parse_error(
+            p,
+            "18,446,744,073,709,551,616",
+            r#"failed to parse "18,446,744,073,709,551,616" as type u64"#,
+        );
+        assert_eq!(p.describe().to_string(), "grouped_u64(',')");
+
+        let p = grouped_i64('.');
+        assert_parse_eq(p, "-1.000.000", -1_000_000);
+        assert_parse_eq(p, "+1.000", 1000);
+        assert_parse_eq(grouped_u64(' '), "10 000", 10_000);
+        assert_parse_eq(grouped_u64('’'), "10’000", 10_000);
+    }
+}
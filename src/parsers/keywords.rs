@@ -0,0 +1,268 @@
+//! `one_of_str(words)`, which matches any one of a list of strings, using a
+//! trie so that it's fast even when there are many strings.
+
+use std::fmt;
+
+use crate::{ParseContext, ParseIter, Parser, Reported, Result};
+
+/// A node in the trie. The root is `nodes[0]`.
+#[derive(Clone, Debug, Default)]
+struct Node {
+    /// Edges to child nodes, sorted by byte.
+    children: Vec<(u8, usize)>,
+    /// If a word ends at this node, its index in `options`.
+    word: Option<usize>,
+}
+
+/// Finds which of a list of words a string starts with.
+#[derive(Clone, Debug)]
+pub(crate) struct Trie {
+    nodes: Vec<Node>,
+}
+
+impl Trie {
+    pub(crate) fn new(words: &[String]) -> Self {
+        let mut nodes = vec![Node::default()];
+        for (index, word) in words.iter().enumerate() {
+            let mut node = 0;
+            for &b in word.as_bytes() {
+                node = match nodes[node].children.binary_search_by_key(&b, |&(c, _)| c) {
+                    Ok(i) => nodes[node].children[i].1,
+                    Err(i) => {
+                        let child = nodes.len();
+                        nodes.push(Node::default());
+                        nodes[node].children.insert(i, (b, child));
+                        child
+                    }
+                };
+            }
+            // If a word appears twice, the first one wins.
+            nodes[node].word.get_or_insert(index);
+        }
+        Trie { nodes }
+    }
+
+    /// Find all the words that `text` starts with. Returns their indexes,
+    /// shortest first.
+    pub(crate) fn prefixes_of(&self, text: &str) -> Vec<usize> {
+        let mut node = &self.nodes[0];
+        let mut found: Vec<usize> = node.word.into_iter().collect();
+        for &b in text.as_bytes() {
+            match node.children.binary_search_by_key(&b, |&(c, _)| c) {
+                Ok(i) => node = &self.nodes[node.children[i].1],
+                Err(_) => break,
+            }
+            found.extend(node.word);
+        }
+        found
+    }
+}
+
+/// The type of parser returned by [`one_of_str()`] and [`one_of_str_map()`].
+#[derive(Clone, Debug)]
+pub struct OneOfStrParser<T = usize> {
+    /// `"one_of_str"` or `"one_of_str_map"`, for `fmt_pattern`.
+    name: &'static str,
+    options: Vec<String>,
+    values: Vec<T>,
+    trie: Trie,
+}
+
+pub struct OneOfStrParseIter<'parse, T> {
+    start: usize,
+    options: &'parse [String],
+    values: &'parse [T],
+    /// Indexes of the options that match, longest last, so that the next one
+    /// to try after backtracking is at the end.
+    matches: Vec<usize>,
+}
+
+impl<T> Parser for OneOfStrParser<T>
+where
+    T: Clone,
+{
+    type Output = T;
+    type RawOutput = (T,);
+    type Iter<'parse>
+        = OneOfStrParseIter<'parse, T>
+    where
+        T: 'parse;
+
+    fn parse_iter<'parse>(
+        &'parse self,
+        context: &mut ParseContext<'parse>,
+        start: usize,
+    ) -> Result<Self::Iter<'parse>, Reported> {
+        let matches = self.trie.prefixes_of(&context.source()[start..]);
+        if matches.is_empty() {
+            return Err(context.error_expected(start, &self.expected()));
+        }
+        Ok(OneOfStrParseIter {
+            start,
+            options: &self.options,
+            values: &self.values,
+            matches,
+        })
+    }
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}({:?})", self.name, self.options)
+    }
+}
+
+impl<T> OneOfStrParser<T> {
+    fn expected(&self) -> String {
+        let options: Vec<String> = self.options.iter().map(|s| format!("{s:?}")).collect();
+        format!("one of {}", options.join(", "))
+    }
+}
+
+impl<'parse, T> ParseIter<'parse> for OneOfStrParseIter<'parse, T>
+where
+    T: Clone,
+{
+    type RawOutput = (T,);
+
+    fn match_end(&self) -> usize {
+        self.start + self.options[*self.matches.last().unwrap()].len()
+    }
+
+    fn backtrack(&mut self, _context: &mut ParseContext<'parse>) -> Result<(), Reported> {
+        self.matches.pop();
+        if self.matches.is_empty() {
+            Err(Reported)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn convert(&self) -> (T,) {
+        (self.values[*self.matches.last().unwrap()].clone(),)
+    }
+}
+
+/// Make a parser that matches any one of the strings in `options` and
+/// produces the index of that string in the list.
+///
+/// This is faster than a long `{"a" => 0, "b" => 1, ...}` alternation, and
+/// the options can be built at run time, so it's also useful when the first
+/// part of a puzzle input defines words used in the rest. The longest option
+/// that matches is tried first; if the rest of the pattern then fails to
+/// match, shorter options are tried.
+///
+/// ```
+/// # use aoc_parse::{parser, prelude::*};
+/// let p = parser!(repeat_sep(one_of_str(["n", "ne", "se", "s", "sw", "nw"]), ","));
+/// assert_eq!(p.parse("ne,ne,s,s").unwrap(), vec![1, 1, 3, 3]);
+///
+/// let (towels, designs) = "r, wr, b, rb\n\nbrwrr\nrrb\n".split_once("\n\n").unwrap();
+/// let towel = one_of_str(towels.split(", "));
+/// let p = parser!(lines(towel+));
+/// assert_eq!(p.parse(designs).unwrap(), vec![vec![2, 0, 1, 0], vec![0, 3]]);
+/// ```
+pub fn one_of_str<I>(options: I) -> OneOfStrParser
+where
+    I: IntoIterator,
+    I::Item: Into<String>,
+{
+    let options: Vec<String> = options.into_iter().map(Into::into).collect();
+    let values = (0..options.len()).collect();
+    let trie = Trie::new(&options);
+    OneOfStrParser {
+        name: "one_of_str",
+        options,
+        values,
+        trie,
+    }
+}
+
+/// Like [`one_of_str()`], but each option comes with a value to produce when
+/// it matches.
+///
+/// ```
+/// # use aoc_parse::{parser, prelude::*};
+/// let p = parser!(lines(
+///     d:one_of_str_map([("forward ", (1, 0)), ("down ", (0, 1)), ("up ", (0, -1))]) n:i32
+///         => (d.0 * n, d.1 * n)
+/// ));
+/// assert_eq!(p.parse("forward 5\nup 3\n").unwrap(), vec![(5, 0), (0, -3)]);
+/// ```
+pub fn one_of_str_map<I, S, T>(options: I) -> OneOfStrParser<T>
+where
+    I: IntoIterator<Item = (S, T)>,
+    S: Into<String>,
+{
+    let (options, values): (Vec<String>, Vec<T>) = options
+        .into_iter()
+        .map(|(option, value)| (option.into(), value))
+        .unzip();
+    let trie = Trie::new(&options);
+    OneOfStrParser {
+        name: "one_of_str_map",
+        options,
+        values,
+        trie,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::{lines, plus};
+    use crate::testing::*;
+
+    #[test]
+    fn test_one_of_str() {
+        let p = one_of_str(["red", "green", "blue"]);
+        assert_parse_eq(&p, "red", 0);
+        assert_parse_eq(&p, "blue", 2);
+        assert_parse_error(
+            &p,
+            "pink",
+            r#"expected one of "red", "green", "blue" at line 1 column 1"#,
+        );
+        assert_parse_error(&p, "gree", r#"expected one of "red", "green", "blue""#);
+        assert_eq!(
+            p.describe().to_string(),
+            r#"one_of_str(["red", "green", "blue"])"#
+        );
+
+        // Owned strings, e.g. from the first section of a puzzle input.
+        let words: Vec<String> = "a ab abc".split(' ').map(String::from).collect();
+        let p = one_of_str(words);
+        assert_parse_eq(&p, "abc", 2);
+        assert_parse_eq(&p, "ab", 1);
+
+        // Backtracks from longer matches to shorter ones.
+        assert_parse_eq(sequence(&p, "bc"), "abc", 0);
+        assert_parse_eq(plus(&p), "abcaba", vec![2, 1, 0]);
+        assert_parse_eq(lines(plus(&p)), "aab\nabca\n", vec![vec![0, 1], vec![2, 0]]);
+        assert_parse_error(&p, "b", r#"expected one of "a", "ab", "abc""#);
+
+        // Duplicates: the first one wins.
+        assert_parse_eq(one_of_str(["x", "y", "x"]), "x", 0);
+
+        // Non-ASCII.
+        assert_parse_eq(one_of_str(["→", "←", "↑"]), "↑", 2);
+    }
+
+    #[test]
+    fn test_one_of_str_map() {
+        let p = one_of_str_map([("hlf", 'h'), ("tpl", 't'), ("inc", 'i'), ("jmp", 'j')]);
+        assert_parse_eq(&p, "inc", 'i');
+        assert_parse_eq(&p, "tpl", 't');
+        assert_parse_error(
+            &p,
+            "jio",
+            r#"expected one of "hlf", "tpl", "inc", "jmp" at line 1 column 1"#,
+        );
+        assert_eq!(
+            p.describe().to_string(),
+            r#"one_of_str_map(["hlf", "tpl", "inc", "jmp"])"#
+        );
+
+        let p = one_of_str_map([("", 0), ("-", -1), ("+", 1)]);
+        assert_parse_eq(sequence(&p, "5"), "-5", -1);
+        assert_parse_eq(sequence(&p, "5"), "5", 0);
+    }
+}
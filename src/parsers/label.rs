@@ -0,0 +1,88 @@
+//! The parser used by `label()`, for custom error messages.
+
+use std::fmt;
+
+use crate::{describe, ParseContext, Parser, Reported, Result};
+
+/// The type of parser returned by [`label()`] and [`Parser::label`].
+#[derive(Clone, Copy)]
+pub struct LabelParser<P> {
+    pub(crate) parser: P,
+    pub(crate) label: &'static str,
+}
+
+impl<P> Parser for LabelParser<P>
+where
+    P: Parser,
+{
+    type Output = P::Output;
+    type RawOutput = P::RawOutput;
+    type Iter<'parse>
+        = P::Iter<'parse>
+    where
+        P: 'parse;
+
+    fn parse_iter<'parse>(
+        &'parse self,
+        context: &mut ParseContext<'parse>,
+        start: usize,
+    ) -> Result<Self::Iter<'parse>, Reported> {
+        self.parser.parse_iter(context, start).map_err(|Reported| {
+            // If the pattern got somewhere before failing, that error is more
+            // useful than the label. Otherwise, replace it.
+            context.relabel_error(start, self.label);
+            Reported
+        })
+    }
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("label(")?;
+        describe::write_pattern(f, &self.parser)?;
+        write!(f, ", {:?})", self.label)
+    }
+}
+
+/// Make a parser that matches the same text as `parser`, but if it fails
+/// without matching anything, the error message says "expected {label}".
+///
+/// ```
+/// # use aoc_parse::{parser, prelude::*};
+/// let p = parser!(label({"inc", "dec"} " " i64, "an instruction"));
+/// assert!(p
+///     .parse("jmp 3")
+///     .unwrap_err()
+///     .to_string()
+///     .starts_with("expected an instruction at line 1 column 1"));
+/// ```
+pub fn label<P: Parser>(parser: P, label: &'static str) -> LabelParser<P> {
+    LabelParser { parser, label }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::{lines, u32};
+    use crate::testing::*;
+
+    #[test]
+    fn test_label() {
+        let p = label(sequence("cpy ", u32), "an instruction");
+        assert_parse_eq(p, "cpy 3", 3);
+        assert_parse_error(p, "jnz 3", "expected an instruction at line 1 column 1");
+
+        // An error after the start of the pattern is more specific, so it's kept.
+        assert_parse_error(
+            p,
+            "cpy x",
+            "expected an unsigned integer at line 1 column 5",
+        );
+
+        // The method form works the same, and it works inside other patterns.
+        let p = lines(u32.label("a line number"));
+        assert_parse_error(p, "1\nx\n", "expected a line number at line 2 column 1");
+        assert_eq!(
+            p.describe().to_string(),
+            r#"lines(label(u32, "a line number"))"#
+        );
+    }
+}
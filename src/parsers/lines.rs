@@ -1,15 +1,22 @@
-//! Parsers that parse lines or groups of lines: `line(p)`, `lines(p)`.
+//! Parsers that parse lines or groups of lines: `line(p)`, `lines(p)`,
+//! `lines_lenient(p)`.
 
-use std::marker::PhantomData;
+use std::{fmt, marker::PhantomData};
 
 use crate::{
+    describe,
     parsers::{star, EmptyParser, RepeatParser},
+    trace,
     types::ParserOutput,
     ParseContext, ParseError, ParseIter, Parser, Reported, Result,
 };
 
 /// This is implemented for `Line` and `Section`, the two region types.
 pub trait Region: Copy + Clone {
+    /// The name of the function that creates this kind of region parser, for
+    /// describing the pattern.
+    const NAME: &'static str;
+
     /// True if `start` is an offset within `source` that's the start of this
     /// type of region.
     ///
@@ -40,12 +47,13 @@ pub trait Region: Copy + Clone {
 pub struct Line;
 
 impl Region for Line {
+    const NAME: &'static str = "line";
+
     fn check_at_start(context: &mut ParseContext, start: usize) -> Result<(), Reported> {
-        let source = context.source();
-        if start == 0 || source[..start].ends_with('\n') {
+        if context.is_line_start(start) {
             Ok(())
         } else {
-            Err(context.report(ParseError::new_bad_line_start(source, start)))
+            Err(context.report(ParseError::new_bad_line_start(start)))
         }
     }
 
@@ -59,7 +67,7 @@ impl Region for Line {
     }
 
     fn report_incomplete_match(context: &mut ParseContext, end: usize) -> Reported {
-        context.report(ParseError::new_line_extra(context.source(), end))
+        context.report(ParseError::new_line_extra(end))
     }
 }
 
@@ -70,12 +78,13 @@ impl Region for Line {
 pub struct Section;
 
 impl Region for Section {
+    const NAME: &'static str = "section";
+
     fn check_at_start(context: &mut ParseContext, start: usize) -> Result<(), Reported> {
-        let source = context.source();
-        if start == 0 || &source[..start] == "\n" || source[..start].ends_with("\n\n") {
+        if context.is_section_start(start) {
             Ok(())
         } else {
-            Err(context.report(ParseError::new_bad_section_start(source, start)))
+            Err(context.report(ParseError::new_bad_section_start(start)))
         }
     }
 
@@ -84,9 +93,9 @@ impl Region for Section {
         // section at end of input. presumably not repeat forever. (why does
         // this not always hang forever if you try to use `sections`?)
         let source = context.source();
-        match source[start..].find("\n\n") {
+        match context.find_blank_line(start) {
             // ending at a blank line
-            Some(index) => Ok((start + index + 1, start + index + 2)),
+            Some(index) => Ok((index + 1, index + 2)),
             // ending at the end of `source`
             None if start < source.len() => Ok((source.len(), source.len())),
             // no end-of-section delimiter found
@@ -95,25 +104,25 @@ impl Region for Section {
     }
 
     fn report_incomplete_match(context: &mut ParseContext, end: usize) -> Reported {
-        context.report(ParseError::new_section_extra(context.source(), end))
+        context.report(ParseError::new_section_extra(end))
     }
 }
 
 /// Match but don't convert; just return the ParseIter on success. Expects all
 /// of `source` to be matched, otherwise it's an error.
-fn match_fully<'parse, R, P>(
+pub(crate) fn match_fully<'parse, R, P>(
     context: &mut ParseContext<'parse>,
     parser: &'parse P,
 ) -> Result<P::Iter<'parse>, Reported>
 where
     R: Region,
-    P: Parser,
+    P: Parser + ?Sized,
 {
     let source = context.source();
-    let mut iter = parser.parse_iter(context, 0)?;
+    let mut iter = trace::parse_iter(parser, context, 0)?;
     while iter.match_end() != source.len() {
         R::report_incomplete_match(context, iter.match_end());
-        iter.backtrack(context)?;
+        trace::backtrack(parser, &mut iter, context)?;
     }
     Ok(iter)
 }
@@ -131,7 +140,8 @@ where
 {
     type RawOutput = (P::Output,);
     type Output = P::Output;
-    type Iter<'parse> = RegionParseIter<'parse, P>
+    type Iter<'parse>
+        = RegionParseIter<'parse, P>
     where
         R: 'parse,
         P: 'parse;
@@ -149,6 +159,20 @@ where
         })?;
         Ok(RegionParseIter { iter, outer_end })
     }
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}(", R::NAME)?;
+        describe::write_pattern(f, &self.parser)?;
+        f.write_str(")")
+    }
+
+    fn fmt_repeated(&self, f: &mut fmt::Formatter<'_>) -> Option<fmt::Result> {
+        // `star(line(p))` is `lines(p)`.
+        let result = write!(f, "{}s(", R::NAME)
+            .and_then(|()| describe::write_pattern(f, &self.parser))
+            .and_then(|()| f.write_str(")"));
+        Some(result)
+    }
 }
 
 pub struct RegionParseIter<'parse, P>
@@ -165,6 +189,10 @@ where
 {
     type RawOutput = (P::Output,);
 
+    fn is_deterministic() -> bool {
+        true
+    }
+
     fn match_end(&self) -> usize {
         self.outer_end
     }
@@ -179,6 +207,95 @@ where
     }
 }
 
+/// The type of parser returned by [`lines_lenient()`].
+#[derive(Copy, Clone)]
+pub struct LenientLinesParser<P> {
+    parser: P,
+}
+
+impl<P> Parser for LenientLinesParser<P>
+where
+    P: Parser,
+{
+    type Output = (Vec<P::Output>, Vec<ParseError>);
+    type RawOutput = ((Vec<P::Output>, Vec<ParseError>),);
+    type Iter<'parse>
+        = LenientLinesParseIter<'parse, P>
+    where
+        P: 'parse;
+
+    fn parse_iter<'parse>(
+        &'parse self,
+        context: &mut ParseContext<'parse>,
+        start: usize,
+    ) -> Result<Self::Iter<'parse>, Reported> {
+        Line::check_at_start(context, start)?;
+
+        let source = context.source();
+        let mut lines = vec![];
+        let mut pos = start;
+        while pos < source.len() {
+            let (inner_end, outer_end) = Line::find_end(context, pos)?;
+            let result = context.try_slice(pos, inner_end, |inner_context| {
+                match_fully::<Line, P>(inner_context, &self.parser)
+            });
+            lines.push((result, outer_end));
+            pos = outer_end;
+        }
+        Ok(LenientLinesParseIter { start, lines })
+    }
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("lines_lenient(")?;
+        describe::write_pattern(f, &self.parser)?;
+        f.write_str(")")
+    }
+}
+
+pub struct LenientLinesParseIter<'parse, P>
+where
+    P: Parser + 'parse,
+{
+    start: usize,
+    /// For each line matched so far, either the match or the reason it didn't
+    /// match; and the offset of the end of the line, including the newline.
+    lines: Vec<(Result<P::Iter<'parse>, ParseError>, usize)>,
+}
+
+impl<'parse, P> ParseIter<'parse> for LenientLinesParseIter<'parse, P>
+where
+    P: Parser,
+{
+    type RawOutput = ((Vec<P::Output>, Vec<ParseError>),);
+
+    fn match_end(&self) -> usize {
+        match self.lines.last() {
+            Some((_, end)) => *end,
+            None => self.start,
+        }
+    }
+
+    fn backtrack(&mut self, _context: &mut ParseContext<'parse>) -> Result<(), Reported> {
+        // Give up the last line, like `lines(p)` would.
+        match self.lines.pop() {
+            Some(_) => Ok(()),
+            None => Err(Reported),
+        }
+    }
+
+    fn convert(&self) -> Self::RawOutput {
+        let mut values = vec![];
+        let mut errors = vec![];
+        for (result, _) in &self.lines {
+            match result {
+                Ok(iter) => values.push(iter.convert().into_user_type()),
+                Err(err) => errors.push(err.clone()),
+            }
+        }
+        ((values, errors),)
+    }
+}
+
 pub type LineParser<P> = RegionParser<Line, P>;
 pub type SectionParser<P> = RegionParser<Section, P>;
 
@@ -217,6 +334,27 @@ pub fn lines<P>(parser: P) -> RepeatParser<LineParser<P>, EmptyParser> {
     star(line(parser))
 }
 
+/// <code>lines_lenient(<var>pattern</var>)</code> matches all remaining lines
+/// of text, like <code>lines(<var>pattern</var>)</code>, but lines that don't
+/// match *pattern* are skipped instead of causing the whole parse to fail.
+///
+/// This produces a pair: a `Vec` of values for the lines that matched, and a
+/// `Vec` of [`ParseError`]s, one for each line that didn't. This is handy for
+/// checking hand-edited input files, since it finds all the bad lines at
+/// once.
+///
+/// ```
+/// # use aoc_parse::{parser, prelude::*};
+/// let p = parser!(lines_lenient(u32 "," u32));
+/// let (points, errors) = p.parse("1,2\n3;4\n5,6\nseven\n").unwrap();
+/// assert_eq!(points, vec![(1, 2), (5, 6)]);
+/// assert_eq!(errors.len(), 2);
+/// assert!(errors[0].to_string().ends_with("at line 2 column 2"));
+/// ```
+pub fn lines_lenient<P>(parser: P) -> LenientLinesParser<P> {
+    LenientLinesParser { parser }
+}
+
 /// <code>section(<var>pattern</var>)</code> matches zero or more nonblank
 /// lines, followed by either a blank line or the end of input. The nonblank
 /// lines must match *pattern*.
@@ -245,8 +383,8 @@ pub fn sections<P>(parser: P) -> RepeatParser<SectionParser<P>, EmptyParser> {
 
 #[cfg(test)]
 mod tests {
-    use super::{line, section};
-    use crate::prelude::u32;
+    use super::{line, lines_lenient, section, sections};
+    use crate::prelude::{u32, u64};
     use crate::testing::*;
 
     #[test]
@@ -278,4 +416,45 @@ mod tests {
         assert_no_parse(p, "sec1\nsec2");
         assert_no_parse(p, "sec1sec2\n\n");
     }
+
+    #[test]
+    fn test_lines_lenient() {
+        let p = lines_lenient(u64);
+        let (values, errors) = p.parse("").unwrap();
+        assert!(values.is_empty() && errors.is_empty());
+
+        let (values, errors) = p.parse("1\n2\nx\n4\n5y\n").unwrap();
+        assert_eq!(values, vec![1, 2, 4]);
+        let messages: Vec<String> = errors.iter().map(|err| err.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "expected an unsigned integer at line 3 column 1",
+                "line(pattern) matched part of the line, but not all of it at line 5 column 2",
+            ]
+        );
+        // All the errors share one copy of the input.
+        assert!(std::sync::Arc::ptr_eq(&errors[0].source, &errors[1].source));
+
+        // Backtracks to let later patterns match.
+        let p = sequence(lines_lenient(u64), line("end"));
+        let ((values, errors), ()) = p.parse("1\nx\n3\nend\n").unwrap();
+        assert_eq!(values, vec![1, 3]);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].location, 2);
+
+        // Errors are located in the whole input, not just the section.
+        let p = sections(lines_lenient(u32));
+        let sections = p.parse("1\n2\n\n3\nx\n").unwrap();
+        assert_eq!(sections[0].0, vec![1, 2]);
+        assert!(sections[0].1.is_empty());
+        let (values, errors) = &sections[1];
+        assert_eq!(values, &vec![3]);
+        assert_eq!(
+            errors[0].to_string(),
+            "expected an unsigned integer at line 5 column 1"
+        );
+        assert_eq!(errors[0].location, 7);
+        assert_eq!(&*errors[0].source, "1\n2\n\n3\nx\n");
+    }
 }
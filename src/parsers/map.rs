@@ -1,5 +1,7 @@
 //! Mapping parsers.
 
+use std::fmt::{self, Display};
+
 use crate::{types::ParserOutput, ParseContext, ParseIter, Parser, Reported, Result};
 
 pub trait Mapping<T> {
@@ -41,7 +43,8 @@ where
 {
     type Output = <F::RawOutput as ParserOutput>::UserType;
     type RawOutput = F::RawOutput;
-    type Iter<'parse> = MapParseIter<'parse, P, F>
+    type Iter<'parse>
+        = MapParseIter<'parse, P, F>
     where
         P: 'parse,
         F: 'parse;
@@ -58,6 +61,12 @@ where
             mapper,
         })
     }
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Mapping doesn't change what text matches, so it doesn't appear in
+        // the description.
+        self.inner.fmt_pattern(f)
+    }
 }
 
 impl<'parse, P, F> ParseIter<'parse> for MapParseIter<'parse, P, F>
@@ -67,6 +76,10 @@ where
 {
     type RawOutput = F::RawOutput;
 
+    fn is_deterministic() -> bool {
+        P::Iter::is_deterministic()
+    }
+
     fn match_end(&self) -> usize {
         self.inner.match_end()
     }
@@ -156,6 +169,156 @@ where
     }
 }
 
+/// The type of parser returned by [`try_map()`]: like `MapParser`, but the
+/// conversion can fail.
+#[derive(Clone, Copy)]
+pub struct TryMapParser<P, F> {
+    inner: P,
+    mapper: F,
+}
+
+pub struct TryMapParseIter<'parse, P, F, T>
+where
+    P: Parser + 'parse,
+{
+    parser: &'parse TryMapParser<P, F>,
+    start: usize,
+    inner: P::Iter<'parse>,
+    /// The converted value of the current match. `convert()` clones it
+    /// rather than calling the closure again, which might not accept the
+    /// same match twice.
+    value: T,
+}
+
+impl<P, F, T, E> TryMapParser<P, F>
+where
+    P: Parser,
+    F: Fn(P::Output) -> Result<T, E>,
+    E: Display,
+{
+    /// Convert the current match of `iter`, backtracking until the conversion
+    /// succeeds or we run out of matches.
+    fn convert_first<'parse>(
+        &'parse self,
+        context: &mut ParseContext<'parse>,
+        start: usize,
+        iter: &mut P::Iter<'parse>,
+    ) -> Result<T, Reported> {
+        loop {
+            match (self.mapper)(iter.convert().into_user_type()) {
+                Ok(value) => return Ok(value),
+                Err(err) => {
+                    context.error_conversion_failed(start, iter.match_end(), format!("{err}"));
+                    iter.backtrack(context)?;
+                }
+            }
+        }
+    }
+}
+
+impl<P, F, T, E> Parser for TryMapParser<P, F>
+where
+    P: Parser,
+    F: Fn(P::Output) -> Result<T, E>,
+    T: Clone,
+    E: Display,
+{
+    type Output = T;
+    type RawOutput = (T,);
+    type Iter<'parse>
+        = TryMapParseIter<'parse, P, F, T>
+    where
+        P: 'parse,
+        F: 'parse;
+
+    fn parse_iter<'parse>(
+        &'parse self,
+        context: &mut ParseContext<'parse>,
+        start: usize,
+    ) -> Result<Self::Iter<'parse>, Reported> {
+        let mut inner = self.inner.parse_iter(context, start)?;
+        let value = self.convert_first(context, start, &mut inner)?;
+        Ok(TryMapParseIter {
+            parser: self,
+            start,
+            inner,
+            value,
+        })
+    }
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt_pattern(f)
+    }
+}
+
+impl<'parse, P, F, T, E> ParseIter<'parse> for TryMapParseIter<'parse, P, F, T>
+where
+    P: Parser,
+    F: Fn(P::Output) -> Result<T, E>,
+    T: Clone,
+    E: Display,
+{
+    type RawOutput = (T,);
+
+    // Not deterministic even if `P` is: the closure may have side effects,
+    // so repeats must keep this iterator rather than matching again.
+
+    fn match_end(&self) -> usize {
+        self.inner.match_end()
+    }
+
+    fn backtrack(&mut self, context: &mut ParseContext<'parse>) -> Result<(), Reported> {
+        self.inner.backtrack(context)?;
+        let value = self
+            .parser
+            .convert_first(context, self.start, &mut self.inner)?;
+        self.value = value;
+        Ok(())
+    }
+
+    fn convert(&self) -> (T,) {
+        (self.value.clone(),)
+    }
+}
+
+/// Produce a new parser that behaves like this parser but additionally
+/// applies the given fallible closure when producing the value. If the
+/// closure returns an error, it's treated as a failure to match, and the
+/// error message becomes part of the resulting `ParseError`.
+///
+/// This is used to implement the `=>?` feature of `parser!`.
+///
+/// ```
+/// # use aoc_parse::{parser, prelude::*};
+/// let p = parser!(
+///     x:u32 "," y:u32 =>? if x < 10 && y < 10 { Ok((x, y)) } else { Err("out of range") }
+/// );
+/// assert_eq!(p.parse("3,4").unwrap(), (3, 4));
+/// assert_eq!(
+///     p.parse("3,40").unwrap_err().to_string(),
+///     r#"failed to convert "3,40": out of range at line 1 column 1"#,
+/// );
+/// ```
+///
+/// Unlike `=>`, the closure is called during matching, as soon as the
+/// pattern matches, even if the overall parse later fails. This is necessary
+/// because a failed conversion has to be able to make the match fail.
+/// The value it returns is kept and cloned into the output, so the closure
+/// runs only once per match even if it has side effects.
+#[doc(hidden)]
+pub fn try_map<P, T, E, F>(parser: P, mapper: F) -> TryMapParser<P, F>
+where
+    P: Parser,
+    F: Fn(P::Output) -> Result<T, E>,
+    T: Clone,
+    E: Display,
+{
+    TryMapParser {
+        inner: parser,
+        mapper,
+    }
+}
+
 /// Return a parser that matches the same strings as `parser`, but after
 /// performing conversion just discards the values and returns `()`.
 #[allow(dead_code)]
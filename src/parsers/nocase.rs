@@ -0,0 +1,129 @@
+//! The parser used by `nocase()`, for case-insensitive literals.
+
+use std::fmt;
+
+use crate::{describe, ParseContext, ParseIter, Parser, Reported, Result};
+
+/// The type of parser returned by [`nocase()`].
+#[derive(Clone, Copy)]
+pub struct NocaseParser<P> {
+    parser: P,
+}
+
+pub struct NocaseParseIter<'parse, P>
+where
+    P: Parser + 'parse,
+{
+    inner: P::Iter<'parse>,
+}
+
+/// Run `f` with case-insensitive matching turned on.
+fn ignoring_case<'parse, T>(
+    context: &mut ParseContext<'parse>,
+    f: impl FnOnce(&mut ParseContext<'parse>) -> T,
+) -> T {
+    let saved = context.set_ignore_case(true);
+    let result = f(context);
+    context.set_ignore_case(saved);
+    result
+}
+
+impl<P> Parser for NocaseParser<P>
+where
+    P: Parser,
+{
+    type Output = P::Output;
+    type RawOutput = P::RawOutput;
+    type Iter<'parse>
+        = NocaseParseIter<'parse, P>
+    where
+        P: 'parse;
+
+    fn parse_iter<'parse>(
+        &'parse self,
+        context: &mut ParseContext<'parse>,
+        start: usize,
+    ) -> Result<Self::Iter<'parse>, Reported> {
+        let inner = ignoring_case(context, |context| self.parser.parse_iter(context, start))?;
+        Ok(NocaseParseIter { inner })
+    }
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("nocase(")?;
+        describe::write_pattern(f, &self.parser)?;
+        f.write_str(")")
+    }
+}
+
+impl<'parse, P> ParseIter<'parse> for NocaseParseIter<'parse, P>
+where
+    P: Parser,
+{
+    type RawOutput = P::RawOutput;
+
+    fn is_deterministic() -> bool {
+        P::Iter::is_deterministic()
+    }
+
+    fn match_end(&self) -> usize {
+        self.inner.match_end()
+    }
+
+    fn backtrack(&mut self, context: &mut ParseContext<'parse>) -> Result<(), Reported> {
+        // Backtracking can match more literals, so it needs the setting too.
+        ignoring_case(context, |context| self.inner.backtrack(context))
+    }
+
+    fn convert(&self) -> P::RawOutput {
+        self.inner.convert()
+    }
+}
+
+/// Make a parser that matches the same text as `parser`, except that string
+/// and character literals in it match regardless of case.
+///
+/// Only literals are affected, not patterns like `upper` or `char_of("xy")`.
+/// Error messages still quote literals as written.
+///
+/// ```
+/// # use aoc_parse::{parser, prelude::*};
+/// let p = parser!(nocase("Valve " string(upper+) " has flow rate=" u32));
+/// assert_eq!(p.parse("valve AA HAS FLOW RATE=0").unwrap(), ("AA".to_string(), 0));
+/// ```
+pub fn nocase<P: Parser>(parser: P) -> NocaseParser<P> {
+    NocaseParser { parser }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::{exact, lines, plus, u32};
+    use crate::testing::*;
+
+    #[test]
+    fn test_nocase() {
+        let p = nocase("Valve");
+        assert_parse_eq(p, "Valve", ());
+        assert_parse_eq(p, "VALVE", ());
+        assert_parse_eq(p, "vAlVe", ());
+        assert_parse_error(p, "Valv", r#"expected "Valve" at line 1 column 1"#);
+        assert_eq!(p.describe().to_string(), r#"nocase("Valve")"#);
+        assert_no_parse("Valve", "valve");
+
+        assert_parse_eq(nocase('x'), "X", ());
+        assert_parse_eq(nocase(exact(String::from("Ünïcödé"))), "üNÏCÖDÉ", ());
+
+        // Applies to literals matched while backtracking, and inside lines.
+        let p = nocase(lines(sequence(plus("ab"), sequence("b=", u32))));
+        assert_parse_eq(
+            p,
+            "ABAbabB=1\nabB=2\n",
+            vec![(vec![(), (), ()], 1), (vec![()], 2)],
+        );
+
+        // Only inside `nocase()`.
+        let p = sequence(nocase("a"), "b");
+        assert_parse_eq(p, "Ab", ());
+        assert_no_parse(p, "AB");
+    }
+}
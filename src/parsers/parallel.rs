@@ -0,0 +1,286 @@
+//! `par_lines(p)` and `par_sections(p)`, which parse each line or section on
+//! a separate rayon task. Enabled by the `rayon` feature.
+
+use std::{fmt, marker::PhantomData};
+
+use rayon::prelude::*;
+
+use crate::{
+    describe,
+    parsers::lines::{match_fully, Line, Region, Section},
+    types::ParserOutput,
+    ParseContext, ParseIter, Parser, Reported, Result,
+};
+
+/// The type of parser returned by [`par_lines()`] and [`par_sections()`].
+#[derive(Copy, Clone)]
+pub struct ParRegionsParser<R: Region, P> {
+    parser: P,
+    phantom: PhantomData<fn() -> R>,
+}
+
+impl<R, P> Parser for ParRegionsParser<R, P>
+where
+    R: Region,
+    P: Parser + Sync,
+    P::Output: Clone + Send,
+{
+    type Output = Vec<P::Output>;
+    type RawOutput = (Vec<P::Output>,);
+    type Iter<'parse>
+        = ParRegionsParseIter<P::Output>
+    where
+        R: 'parse,
+        P: 'parse;
+
+    fn parse_iter<'parse>(
+        &'parse self,
+        context: &mut ParseContext<'parse>,
+        start: usize,
+    ) -> Result<Self::Iter<'parse>, Reported> {
+        // Find all the regions first. This uses a scratch context so that the
+        // error at the end of the last region isn't reported if some earlier
+        // region fails to match.
+        let mut scratch = context.detach();
+        let mut regions = vec![];
+        let mut pos = start;
+        while R::check_at_start(&mut scratch, pos).is_ok() {
+            let Ok((inner_end, outer_end)) = R::find_end(&mut scratch, pos) else {
+                break;
+            };
+            regions.push((pos, inner_end, outer_end));
+            pos = outer_end;
+        }
+
+        let parser = &self.parser;
+        let results: Vec<Option<P::Output>> = if context.has_rule_sets() {
+            // The rules can't be used from other threads (see
+            // `RuleSetParser`), so parse the regions here, one at a time.
+            regions
+                .iter()
+                .map(|&(region_start, inner_end, _)| {
+                    context
+                        .try_slice(region_start, inner_end, |inner_context| {
+                            let iter = match_fully::<R, P>(inner_context, parser)?;
+                            Ok(iter.convert().into_user_type())
+                        })
+                        .ok()
+                })
+                .take_while(Option::is_some)
+                .collect()
+        } else {
+            // Each region gets its own context, but over the same input and
+            // with the same settings, so positions and line numbers come out
+            // the same as in `lines(p)` or `sections(p)`.
+            let shared = context.share_source();
+            regions
+                .par_iter()
+                .map(|&(region_start, inner_end, _)| {
+                    let mut region_context = shared.slice(region_start, inner_end);
+                    let iter = match_fully::<R, P>(&mut region_context, parser).ok()?;
+                    Some(iter.convert().into_user_type())
+                })
+                .collect()
+        };
+
+        let mut values = vec![];
+        let mut ends = vec![];
+        for (value, &(_, _, outer_end)) in results.into_iter().zip(&regions) {
+            let Some(value) = value else {
+                break;
+            };
+            values.push(value);
+            ends.push(outer_end);
+        }
+
+        // Matching stopped at the region after the last one that matched.
+        // Match that region again, sequentially and in `context`, so that the
+        // same error is reported as if we'd used `lines(p)` or `sections(p)`.
+        let stop = ends.last().copied().unwrap_or(start);
+        let _ = R::check_at_start(context, stop).and_then(|()| {
+            let (inner_end, _) = R::find_end(context, stop)?;
+            context.with_slice(stop, inner_end, |inner_context| {
+                match_fully::<R, P>(inner_context, parser).map(|_| ())
+            })
+        });
+
+        Ok(ParRegionsParseIter {
+            start,
+            values,
+            ends,
+        })
+    }
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "par_{}s(", R::NAME)?;
+        describe::write_pattern(f, &self.parser)?;
+        f.write_str(")")
+    }
+}
+
+pub struct ParRegionsParseIter<T> {
+    start: usize,
+    values: Vec<T>,
+    /// The end of each region matched so far, including the delimiter.
+    ends: Vec<usize>,
+}
+
+impl<'parse, T> ParseIter<'parse> for ParRegionsParseIter<T>
+where
+    T: Clone,
+{
+    type RawOutput = (Vec<T>,);
+
+    fn match_end(&self) -> usize {
+        self.ends.last().copied().unwrap_or(self.start)
+    }
+
+    fn backtrack(&mut self, _context: &mut ParseContext<'parse>) -> Result<(), Reported> {
+        // Give up the last region, like `lines(p)` would.
+        self.values.pop().ok_or(Reported)?;
+        self.ends.pop();
+        Ok(())
+    }
+
+    fn convert(&self) -> Self::RawOutput {
+        (self.values.clone(),)
+    }
+}
+
+/// <code>par_lines(<var>pattern</var>)</code> matches the same text and
+/// produces the same `Vec` as <code>lines(<var>pattern</var>)</code>, but
+/// parses the lines in parallel, using rayon.
+///
+/// Rules can't be used from other threads, so inside a `parser!` that has
+/// `rule`s, this parses the lines one at a time, like `lines()`.
+///
+/// ```
+/// # use aoc_parse::{parser, prelude::*};
+/// let p = parser!(par_lines(u64 " " u64));
+/// assert_eq!(p.parse("1 2\n3 4\n").unwrap(), vec![(1, 2), (3, 4)]);
+/// ```
+pub fn par_lines<P>(parser: P) -> ParRegionsParser<Line, P> {
+    ParRegionsParser {
+        parser,
+        phantom: PhantomData,
+    }
+}
+
+/// <code>par_sections(<var>pattern</var>)</code> matches the same text and
+/// produces the same `Vec` as <code>sections(<var>pattern</var>)</code>, but
+/// parses the sections in parallel, using rayon.
+///
+/// Rules can't be used from other threads, so inside a `parser!` that has
+/// `rule`s, this parses the sections one at a time, like `sections()`.
+pub fn par_sections<P>(parser: P) -> ParRegionsParser<Section, P> {
+    ParRegionsParser {
+        parser,
+        phantom: PhantomData,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ops::Range;
+
+    use crate::parsers::{
+        alpha, line, lines, lines_lenient, nocase, plus, sections, u32, u64, RuleParser,
+        RuleSetBuilder, StrSlice,
+    };
+    use crate::prelude::str_slice;
+    use crate::testing::*;
+    use crate::ParseError;
+
+    #[test]
+    fn test_par_lines() {
+        let input: String = (0..1000).map(|i| format!("{i}\n")).collect();
+        let expected: Vec<u64> = (0..1000).collect();
+        assert_parse_eq(par_lines(u64), &input, expected);
+        assert_parse_eq(par_lines(u64), "", vec![]);
+        assert_parse_eq(par_lines(u64), "1\n2", vec![1, 2]);
+
+        // Backtracking gives up lines at the end.
+        let p = sequence(par_lines(u64), line("end"));
+        assert_parse_eq(p, "1\n2\nend\n", (vec![1, 2], ()));
+        assert_eq!(par_lines(u64).describe().to_string(), "par_lines(u64)");
+    }
+
+    #[test]
+    fn test_par_lines_nocase() {
+        let p = nocase(par_lines(sequence("a", u32)));
+        assert_parse_eq(p, "A1\na2\n", vec![1, 2]);
+        assert_parse_error(p, "A1\nb2\n", "expected \"a\" at line 2 column 1");
+    }
+
+    #[test]
+    fn test_par_lines_in_rule_set() {
+        let mut builder = RuleSetBuilder::new();
+        let num: RuleParser<u64> = builder.new_rule("num");
+        let builder = builder.assign_parser_for_rule(&num, u64);
+        let p = builder.build(par_sections(par_lines(num)));
+        assert_parse_eq(&p, "1\n2\n\n3\n", vec![vec![1, 2], vec![3]]);
+        assert_parse_error(
+            &p,
+            "1\n2\n\n3\nx\n",
+            "expected an unsigned integer at line 5 column 1",
+        );
+    }
+
+    #[test]
+    fn test_par_positions() {
+        // Values that depend on where they were matched come out the same as
+        // with `lines(p)` and `sections(p)`.
+        let input = "ab\ncd\n\nef\nx\n";
+        let ranges = |slices: Vec<StrSlice>| -> Vec<Range<usize>> {
+            slices.iter().map(StrSlice::range).collect()
+        };
+        let par = par_sections(par_lines(str_slice(plus(alpha))));
+        let seq = sections(lines(str_slice(plus(alpha))));
+        let par_ranges: Vec<_> = par.parse(input).unwrap().into_iter().map(ranges).collect();
+        let seq_ranges: Vec<_> = seq.parse(input).unwrap().into_iter().map(ranges).collect();
+        assert_eq!(par_ranges, vec![vec![0..2, 3..5], vec![7..9, 10..11]]);
+        assert_eq!(par_ranges, seq_ranges);
+
+        let input = "1\n2\n\n3\nx\n";
+        let messages = |sections: Vec<(Vec<u32>, Vec<ParseError>)>| -> Vec<String> {
+            sections
+                .iter()
+                .flat_map(|(_, errors)| errors.iter().map(|err| err.to_string()))
+                .collect()
+        };
+        let par = messages(par_sections(lines_lenient(u32)).parse(input).unwrap());
+        let seq = messages(sections(lines_lenient(u32)).parse(input).unwrap());
+        assert_eq!(par, vec!["expected an unsigned integer at line 5 column 1"]);
+        assert_eq!(par, seq);
+    }
+
+    #[test]
+    fn test_par_errors() {
+        fn assert_same_error<P: Parser, Q: Parser>(par: P, seq: Q, input: &str) {
+            assert_eq!(
+                par.parse(input).err().map(|err| err.to_string()),
+                seq.parse(input).err().map(|err| err.to_string()),
+            );
+        }
+
+        for input in [
+            "1\n2\nx\n4\n",
+            "1\n2\n3y\n",
+            "1\n\n",
+            "1\n2\n3\n",
+            "1\n\n2\n\n\n",
+        ] {
+            assert_same_error(
+                sequence(par_lines(u64), "!"),
+                sequence(lines(u64), "!"),
+                input,
+            );
+            assert_same_error(
+                sequence(par_sections(lines(u64)), "!"),
+                sequence(sections(lines(u64)), "!"),
+                input,
+            );
+        }
+    }
+}
@@ -1,11 +1,19 @@
-use std::{num::ParseIntError, str::FromStr};
+use std::{
+    fmt::{self, Display},
+    marker::PhantomData,
+    str::FromStr,
+};
 
 use lazy_static::lazy_static;
 use num_bigint::{BigInt, BigUint, ParseBigIntError};
-use num_traits::Num;
+use num_integer::Integer;
+use num_rational::{BigRational, ParseRatioError, Ratio};
+use num_traits::{checked_pow, CheckedMul, FromPrimitive, Num, PrimInt};
 use regex::Regex;
 
-use crate::{parsers::regex::RegexParser, ParseContext, ParseIter, Reported};
+use crate::{
+    describe, parsers::regex::RegexParser, ParseContext, ParseIter, Parser, Reported, Result,
+};
 
 /// A trivial ParseIter that presents exactly one match and holds a
 /// pre-converted value.
@@ -24,6 +32,10 @@ where
 {
     type RawOutput = (T,);
 
+    fn is_deterministic() -> bool {
+        true
+    }
+
     fn match_end(&self) -> usize {
         self.end
     }
@@ -37,6 +49,274 @@ where
     }
 }
 
+// --- Fixed-width integers
+
+/// Parser for the built-in integer types, like `u64` and `i32_hex`.
+///
+/// This matches the same text as the regex `[+-]?[0-9]+` (or `[0-9]+` for
+/// unsigned types, and the appropriate digits for other radixes) but scans
+/// and converts the digits by hand, which is much faster. On overflow, it
+/// falls back on the standard library's `from_str_radix` to produce the same
+/// error message as before.
+pub struct IntParser<T> {
+    pub(crate) name: &'static str,
+    pub(crate) noun: &'static str,
+    pub(crate) radix: u32,
+    /// True for parsers made by `int_radix()`, whose messages say what the
+    /// radix is.
+    pub(crate) show_radix: bool,
+    pub(crate) signed: bool,
+    pub(crate) phantom: PhantomData<fn() -> T>,
+}
+
+// Manual Clone impl because `#[derive(Clone)]` is buggy in this case.
+impl<T> Clone for IntParser<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for IntParser<T> {}
+
+impl<T> Parser for IntParser<T>
+where
+    T: PrimInt,
+    T::FromStrRadixErr: Display,
+{
+    type Output = T;
+    type RawOutput = (T,);
+    type Iter<'parse>
+        = BasicParseIter<T>
+    where
+        T: 'parse;
+
+    fn parse_iter<'parse>(
+        &'parse self,
+        context: &mut ParseContext<'parse>,
+        start: usize,
+    ) -> Result<Self::Iter<'parse>, Reported> {
+        let source = context.source();
+        let bytes = source.as_bytes();
+        let mut end = start;
+        let negative = match bytes.get(end) {
+            Some(b'-') if self.signed => {
+                end += 1;
+                true
+            }
+            Some(b'+') if self.signed => {
+                end += 1;
+                false
+            }
+            _ => false,
+        };
+
+        // `None` means the value overflowed.
+        let radix = T::from(self.radix);
+        let mut value = Some(T::zero());
+        let digits_start = end;
+        while let Some(d) = bytes
+            .get(end)
+            .and_then(|&b| char::from(b).to_digit(self.radix))
+        {
+            value = value.and_then(|v| {
+                let v = v.checked_mul(&radix?)?;
+                let d = T::from(d)?;
+                if negative {
+                    v.checked_sub(&d)
+                } else {
+                    v.checked_add(&d)
+                }
+            });
+            end += 1;
+        }
+        if end == digits_start {
+            if self.show_radix {
+                let noun = format!("{} in base {}", self.noun, self.radix);
+                return Err(context.error_expected(start, &noun));
+            }
+            return Err(context.error_expected(start, self.noun));
+        }
+
+        match value {
+            Some(value) => Ok(BasicParseIter { end, value }),
+            None => match T::from_str_radix(&source[start..end], self.radix) {
+                Ok(value) => Ok(BasicParseIter { end, value }),
+                Err(err) => Err(context.error_from_str_failed(
+                    start,
+                    end,
+                    describe::type_name::<T>(),
+                    format!("{err}"),
+                )),
+            },
+        }
+    }
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.show_radix {
+            write!(f, "{}({})", self.name, self.radix)
+        } else {
+            f.write_str(self.name)
+        }
+    }
+}
+
+macro_rules! int_parsers {
+    ( $( $ty:ident )+ , $signed:expr, $noun:literal) => {
+        $(
+            /// Parse a decimal integer of a primitive type. This accepts the
+            /// same strings as the type's `FromStr` implementation in the
+            /// Rust standard library, except that a leading `+` sign is only
+            /// accepted for signed types.
+            #[allow(non_upper_case_globals)]
+            pub const $ty: IntParser<$ty> = IntParser {
+                name: stringify!($ty),
+                noun: $noun,
+                radix: 10,
+                show_radix: false,
+                signed: $signed,
+                phantom: PhantomData,
+            };
+        )+
+    };
+}
+
+int_parsers!(u8 u16 u32 u64 u128 usize, false, "an unsigned integer");
+int_parsers!(i8 i16 i32 i64 i128 isize, true, "an integer");
+
+/// Make a parser for integers written in the given `radix`, which must be
+/// in the range 2 to 36. Digits above 9 are the letters `a` to `z`, in
+/// either case. Produces a value of type `T`, which can be any primitive
+/// integer type; if `T` is signed, a leading `+` or `-` sign is accepted.
+///
+/// ```
+/// # use aoc_parse::{parser, prelude::*};
+/// let base36 = int_radix::<u64>(36);
+/// assert_eq!(base36.parse("zz").unwrap(), 36 * 36 - 1);
+/// let p = parser!(lines(base36));
+/// assert_eq!(p.parse("10\nAoC\n").unwrap(), vec![36, 13836]);
+/// ```
+///
+/// # Panics
+///
+/// If `radix` is not in the range 2 to 36.
+pub fn int_radix<T: PrimInt>(radix: u32) -> IntParser<T> {
+    assert!(
+        (2..=36).contains(&radix),
+        "int_radix: radix must be in the range 2 to 36, not {radix}"
+    );
+    let signed = T::min_value() < T::zero();
+    IntParser {
+        name: "int_radix",
+        noun: if signed {
+            "an integer"
+        } else {
+            "an unsigned integer"
+        },
+        radix,
+        show_radix: true,
+        signed,
+        phantom: PhantomData,
+    }
+}
+
+/// The type of parser returned by [`digits_from()`].
+#[derive(Clone, Debug)]
+pub struct DigitsFromParser<T> {
+    digits: Vec<char>,
+    offset: i64,
+    phantom: PhantomData<fn() -> T>,
+}
+
+impl<T> Parser for DigitsFromParser<T>
+where
+    T: PrimInt,
+{
+    type Output = T;
+    type RawOutput = (T,);
+    type Iter<'parse>
+        = BasicParseIter<T>
+    where
+        T: 'parse;
+
+    fn parse_iter<'parse>(
+        &'parse self,
+        context: &mut ParseContext<'parse>,
+        start: usize,
+    ) -> Result<Self::Iter<'parse>, Reported> {
+        let source = context.source();
+        let base = self.digits.len() as i64;
+
+        // `None` means the value overflowed.
+        let mut value = Some(T::zero());
+        let mut end = start;
+        for c in source[start..].chars() {
+            let Some(index) = self.digits.iter().position(|&d| d == c) else {
+                break;
+            };
+            let d = index as i64 + self.offset;
+            value = value.and_then(|v| {
+                let v = v.checked_mul(&T::from(base)?)?;
+                if d < 0 {
+                    v.checked_sub(&T::from(-d)?)
+                } else {
+                    v.checked_add(&T::from(d)?)
+                }
+            });
+            end += c.len_utf8();
+        }
+        if end == start {
+            let digits: String = self.digits.iter().collect();
+            let noun = format!("a number made of the digits {digits:?}");
+            return Err(context.error_expected(start, &noun));
+        }
+        match value {
+            Some(value) => Ok(BasicParseIter { end, value }),
+            None => Err(context.error_from_str_failed(
+                start,
+                end,
+                describe::type_name::<T>(),
+                "number out of range for the target type".to_string(),
+            )),
+        }
+    }
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits: String = self.digits.iter().collect();
+        write!(f, "digits_from({digits:?}, {})", self.offset)
+    }
+}
+
+/// Make a parser for numbers written with a custom set of digits. The base
+/// is the number of characters in `digits`, and the value of each digit is
+/// its index in `digits` plus `offset`, which can be negative.
+///
+/// Produces a value of type `T`, which can be any primitive integer type.
+///
+/// ```
+/// # use aoc_parse::{parser, prelude::*};
+/// // "SNAFU" numbers: base 5, with digits worth -2 to 2.
+/// let snafu = digits_from::<i64>("=-012", -2);
+/// assert_eq!(snafu.parse("1=-0-2").unwrap(), 1747);
+///
+/// // Balanced ternary.
+/// let ternary = digits_from::<i32>("-0+", -1);
+/// let p = parser!(repeat_sep(ternary, " "));
+/// assert_eq!(p.parse("+- 0 -").unwrap(), vec![2, 0, -1]);
+/// ```
+///
+/// # Panics
+///
+/// If `digits` has fewer than two characters.
+pub fn digits_from<T: PrimInt>(digits: &str, offset: i64) -> DigitsFromParser<T> {
+    let digits: Vec<char> = digits.chars().collect();
+    assert!(digits.len() >= 2, "digits_from: need at least two digits");
+    DigitsFromParser {
+        digits,
+        offset,
+        phantom: PhantomData,
+    }
+}
+
 // --- Global regexes that are compiled on first use
 
 macro_rules! regexes {
@@ -60,19 +340,23 @@ regexes! {
     int_bin_regex = r"\A[+-]?[01]+";
     uint_hex_regex = r"\A[0-9A-Fa-f]+";
     int_hex_regex = r"\A[+-]?[0-9A-Fa-f]+";
+    rational_regex = r"\A[+-]?[0-9]+(?:/[0-9]+)?";
+    decimal_regex = r"\A[+-]?[0-9]+(?:\.[0-9]+)?(?:[eE][+-]?[0-9]+)?";
     float_regex = r"(?i)\A[+-]?(?:infinity|inf|nan|(?:[0-9]+(?:\.[0-9]*)?|\.[0-9]+)(?:e[+-]?[0-9]+)?)";
 }
 
 // --- Parsers that use FromStr
 
 macro_rules! from_str_parse_impl {
-        ( $( $ty:ident )+ , $re_name:ident) => {
+        ( $( $ty:ident )+ , $re_name:ident, $noun:literal) => {
             $(
                 /// Parse a value of a primitive type (using its `FromStr`
                 /// implementation in the Rust standard library).
                 #[allow(non_upper_case_globals)]
                 pub const $ty: RegexParser<$ty, <$ty as FromStr>::Err> =
                     RegexParser {
+                        name: stringify!($ty),
+                        noun: $noun,
                         regex: $re_name,
                         parse_fn: <$ty as FromStr>::from_str,
                     };
@@ -80,16 +364,16 @@ macro_rules! from_str_parse_impl {
         };
     }
 
-from_str_parse_impl!(u8 u16 u32 u64 u128 usize, uint_regex);
-from_str_parse_impl!(i8 i16 i32 i64 i128 isize, int_regex);
-from_str_parse_impl!(f32 f64, float_regex);
-from_str_parse_impl!(bool, bool_regex);
+from_str_parse_impl!(f32 f64, float_regex, "a number");
+from_str_parse_impl!(bool, bool_regex, "true or false");
 
 /// Parse a BigUint (using its `FromStr` implementation in the `num-bigint`
 /// crate, except that underscores between digits are not accepted and a
 /// leading `+` sign is not accepted).
 #[allow(non_upper_case_globals)]
 pub const big_uint: RegexParser<BigUint, <BigUint as FromStr>::Err> = RegexParser {
+    name: "big_uint",
+    noun: "an unsigned integer",
     regex: uint_regex,
     parse_fn: <BigUint as FromStr>::from_str,
 };
@@ -98,6 +382,8 @@ pub const big_uint: RegexParser<BigUint, <BigUint as FromStr>::Err> = RegexParse
 /// crate, except that underscores between digits are not accepted).
 #[allow(non_upper_case_globals)]
 pub const big_int: RegexParser<BigInt, <BigInt as FromStr>::Err> = RegexParser {
+    name: "big_int",
+    noun: "an integer",
     regex: int_regex,
     parse_fn: <BigInt as FromStr>::from_str,
 };
@@ -105,47 +391,71 @@ pub const big_int: RegexParser<BigInt, <BigInt as FromStr>::Err> = RegexParser {
 // --- Parsers for `_bin` and `_hex` integers
 
 macro_rules! from_str_radix_parsers {
-    ( $( ( $ty:ident , $bin:ident , $hex:ident ) ),* ; $bin_re:ident, $hex_re:ident ) => {
+    ( $( ( $ty:ident , $bin:ident , $oct:ident , $hex:ident ) ),* ; $signed:expr ) => {
         $(
-            /// Parse an integer written in base 2, using the `from_str_radix`
-            /// static method from the Rust standard library.
+            /// Parse an integer written in base 2. This accepts the same
+            /// strings as the `from_str_radix` static method from the Rust
+            /// standard library, except that a leading `+` sign is only
+            /// accepted for signed types.
+            #[allow(non_upper_case_globals)]
+            pub const $bin: IntParser<$ty> = IntParser {
+                name: stringify!($bin),
+                noun: concat!("a binary ", stringify!($ty)),
+                radix: 2,
+                show_radix: false,
+                signed: $signed,
+                phantom: PhantomData,
+            };
+
+            /// Parse an integer written in base 8. This accepts the same
+            /// strings as the `from_str_radix` static method from the Rust
+            /// standard library, except that a leading `+` sign is only
+            /// accepted for signed types.
             #[allow(non_upper_case_globals)]
-            pub const $bin: RegexParser<$ty, ParseIntError> = RegexParser {
-                regex: $bin_re,
-                parse_fn: |s| $ty::from_str_radix(s, 2),
+            pub const $oct: IntParser<$ty> = IntParser {
+                name: stringify!($oct),
+                noun: concat!("an octal ", stringify!($ty)),
+                radix: 8,
+                show_radix: false,
+                signed: $signed,
+                phantom: PhantomData,
             };
 
-            /// Parse an integer written in base 16, using the `from_str_radix`
-            /// static method from the Rust standard library.
+            /// Parse an integer written in base 16. This accepts the same
+            /// strings as the `from_str_radix` static method from the Rust
+            /// standard library, except that a leading `+` sign is only
+            /// accepted for signed types.
             #[allow(non_upper_case_globals)]
-            pub const $hex: RegexParser<$ty, ParseIntError> = RegexParser {
-                regex: $hex_re,
-                parse_fn: |s| $ty::from_str_radix(s, 16),
+            pub const $hex: IntParser<$ty> = IntParser {
+                name: stringify!($hex),
+                noun: concat!("a hexadecimal ", stringify!($ty)),
+                radix: 16,
+                show_radix: false,
+                signed: $signed,
+                phantom: PhantomData,
             };
         )*
     }
 }
 
 from_str_radix_parsers!(
-    (u8, u8_bin, u8_hex),
-    (u16, u16_bin, u16_hex),
-    (u32, u32_bin, u32_hex),
-    (u64, u64_bin, u64_hex),
-    (u128, u128_bin, u128_hex),
-    (usize, usize_bin, usize_hex);
-    uint_bin_regex,
-    uint_hex_regex
+    (u8, u8_bin, u8_oct, u8_hex),
+    (u16, u16_bin, u16_oct, u16_hex),
+    (u32, u32_bin, u32_oct, u32_hex),
+    (u64, u64_bin, u64_oct, u64_hex),
+    (u128, u128_bin, u128_oct, u128_hex),
+    (usize, usize_bin, usize_oct, usize_hex);
+    false
 );
 
 from_str_radix_parsers!(
-    (i8, i8_bin, i8_hex),
-    (i16, i16_bin, i16_hex),
-    (i32, i32_bin, i32_hex),
-    (i64, i64_bin, i64_hex),
-    (i128, i128_bin, i128_hex),
-    (isize, isize_bin, isize_hex);
-    int_bin_regex,
-    int_hex_regex
+    (i8, i8_bin, i8_oct, i8_hex),
+    (i16, i16_bin, i16_oct, i16_hex),
+    (i32, i32_bin, i32_oct, i32_hex),
+    (i64, i64_bin, i64_oct, i64_hex),
+    (i128, i128_bin, i128_oct, i128_hex),
+    (isize, isize_bin, isize_oct, isize_hex);
+    true
 );
 
 /// Parse a [`BigUint`] written in base 2 (using its [`Num`] impl from the
@@ -153,6 +463,8 @@ from_str_radix_parsers!(
 /// accepted and a leading `+` sign is not accepted).
 #[allow(non_upper_case_globals)]
 pub const big_uint_bin: RegexParser<BigUint, ParseBigIntError> = RegexParser {
+    name: "big_uint_bin",
+    noun: "a binary unsigned integer",
     regex: uint_bin_regex,
     parse_fn: |s| BigUint::from_str_radix(s, 2),
 };
@@ -162,6 +474,8 @@ pub const big_uint_bin: RegexParser<BigUint, ParseBigIntError> = RegexParser {
 /// accepted and a leading `+` sign is not accepted).
 #[allow(non_upper_case_globals)]
 pub const big_uint_hex: RegexParser<BigUint, ParseBigIntError> = RegexParser {
+    name: "big_uint_hex",
+    noun: "a hexadecimal unsigned integer",
     regex: uint_hex_regex,
     parse_fn: |s| BigUint::from_str_radix(s, 16),
 };
@@ -171,6 +485,8 @@ pub const big_uint_hex: RegexParser<BigUint, ParseBigIntError> = RegexParser {
 /// accepted).
 #[allow(non_upper_case_globals)]
 pub const big_int_bin: RegexParser<BigInt, ParseBigIntError> = RegexParser {
+    name: "big_int_bin",
+    noun: "a binary integer",
     regex: int_bin_regex,
     parse_fn: |s| BigInt::from_str_radix(s, 2),
 };
@@ -180,10 +496,114 @@ pub const big_int_bin: RegexParser<BigInt, ParseBigIntError> = RegexParser {
 /// accepted).
 #[allow(non_upper_case_globals)]
 pub const big_int_hex: RegexParser<BigInt, ParseBigIntError> = RegexParser {
+    name: "big_int_hex",
+    noun: "a hexadecimal integer",
     regex: int_hex_regex,
     parse_fn: |s| BigInt::from_str_radix(s, 16),
 };
 
+// --- Exact fractions and decimals
+
+/// Parse a fraction like `-3/4`, or a plain integer like `7`, as a
+/// [`Ratio<i64>`][Ratio] (using its `FromStr` impl from the `num-rational`
+/// crate). The denominator can't have a sign. It's an error if it's zero.
+#[allow(non_upper_case_globals)]
+pub const rational: RegexParser<Ratio<i64>, ParseRatioError> = RegexParser {
+    name: "rational",
+    noun: "a fraction",
+    regex: rational_regex,
+    parse_fn: <Ratio<i64> as FromStr>::from_str,
+};
+
+/// Like [`rational`], but the result is a [`BigRational`], so the numerator
+/// and denominator can be any size.
+#[allow(non_upper_case_globals)]
+pub const big_rational: RegexParser<BigRational, ParseRatioError> = RegexParser {
+    name: "big_rational",
+    noun: "a fraction",
+    regex: rational_regex,
+    parse_fn: <BigRational as FromStr>::from_str,
+};
+
+/// Convert a string that matches `decimal_regex` to an exact fraction.
+fn parse_decimal<T>(s: &str) -> Result<Ratio<T>, String>
+where
+    T: Clone + Integer + CheckedMul + FromPrimitive + FromStr,
+    T::Err: Display,
+{
+    let (mantissa, exponent) = match s.find(['e', 'E']) {
+        Some(i) => (&s[..i], &s[i + 1..]),
+        None => (s, "0"),
+    };
+    let (whole, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+
+    // The value is `digits * 10^scale`. Trailing zeros are moved into
+    // `scale`, so that a number like `1.000000000000000000000` fits.
+    let all_digits = format!("{whole}{frac}");
+    let trimmed = all_digits.trim_end_matches('0');
+    let scale = exponent
+        .parse::<i64>()
+        .ok()
+        .and_then(|e| e.checked_sub(frac.len() as i64))
+        .and_then(|e| e.checked_add((all_digits.len() - trimmed.len()) as i64))
+        .filter(|scale| scale.unsigned_abs() <= u16::MAX as u64)
+        .ok_or_else(|| "exponent too large".to_string())?;
+    if !trimmed.contains(|c: char| c.is_ascii_digit()) {
+        return Ok(Ratio::from_integer(T::zero()));
+    }
+    let digits: T = trimmed.parse().map_err(|err| format!("{err}"))?;
+
+    let too_large = || "number too large to fit in target type".to_string();
+    let ten = T::from_u8(10).unwrap();
+    if scale >= 0 {
+        let power = checked_pow(ten, scale as usize).ok_or_else(too_large)?;
+        let numer = digits.checked_mul(&power).ok_or_else(too_large)?;
+        return Ok(Ratio::from_integer(numer));
+    }
+
+    // Reduce `digits / 10^-scale` before computing the denominator, which
+    // might not fit otherwise: `5e-19` is `1 / (2 * 10^18)`.
+    let mut numer = digits;
+    let mut exponents = [
+        (T::from_u8(2).unwrap(), -scale as usize),
+        (T::from_u8(5).unwrap(), -scale as usize),
+    ];
+    for (factor, exp) in &mut exponents {
+        while *exp > 0 && numer.is_multiple_of(factor) {
+            numer = numer / factor.clone();
+            *exp -= 1;
+        }
+    }
+    let mut denom = T::one();
+    for (factor, exp) in exponents {
+        let power = checked_pow(factor, exp).ok_or_else(too_large)?;
+        denom = denom.checked_mul(&power).ok_or_else(too_large)?;
+    }
+    Ok(Ratio::new_raw(numer, denom))
+}
+
+/// Parse a decimal number like `3.25`, `-7`, or `1.5e-3` exactly, as a
+/// [`Ratio<i64>`][Ratio]. Unlike `f64`, there's no rounding: `0.1` is
+/// exactly one tenth. It's an error if the numerator or denominator doesn't
+/// fit in an `i64`.
+#[allow(non_upper_case_globals)]
+pub const decimal: RegexParser<Ratio<i64>, String> = RegexParser {
+    name: "decimal",
+    noun: "a decimal number",
+    regex: decimal_regex,
+    parse_fn: parse_decimal::<i64>,
+};
+
+/// Like [`decimal`], but the result is a [`BigRational`], so any number of
+/// digits is accepted.
+#[allow(non_upper_case_globals)]
+pub const big_decimal: RegexParser<BigRational, String> = RegexParser {
+    name: "big_decimal",
+    noun: "a decimal number",
+    regex: decimal_regex,
+    parse_fn: parse_decimal::<BigInt>,
+};
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -200,6 +620,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::needless_borrows_for_generic_args)]
     fn test_parse_hex() {
         assert_no_parse(&i32_hex, "+");
         assert_no_parse(&i32_hex, "-");
@@ -227,6 +648,104 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_radix() {
+        assert_parse_eq(u32_oct, "777", 0o777);
+        assert_parse_eq(i16_oct, "-10", -8);
+        assert_parse_error(u8_oct, "8", "expected an octal u8 at");
+        assert_parse_error(u8_oct, "400", "failed to parse \"400\" as type u8");
+
+        let p = int_radix::<u64>(36);
+        assert_parse_eq(p, "Zz", 36 * 36 - 1);
+        assert_no_parse(p, "-1");
+        assert_parse_error(p, "!", "expected an unsigned integer in base 36 at");
+        assert_eq!(p.describe().to_string(), "int_radix(36)");
+        let p = int_radix::<i8>(3);
+        assert_parse_eq(p, "-11120", -123);
+        assert_parse_eq(sequence(p, "3"), "+123", 5);
+        assert_parse_error(p, "12000", "failed to parse \"12000\" as type i8");
+
+        let p = digits_from::<i64>("=-012", -2);
+        assert_parse_eq(&p, "2=-01", 976);
+        assert_parse_eq(&p, "1121-1110-1=0", 314159265);
+        assert_parse_error(
+            &p,
+            "3",
+            r#"expected a number made of the digits "=-012" at"#,
+        );
+        assert_eq!(p.describe().to_string(), r#"digits_from("=-012", -2)"#);
+        assert_parse_error(
+            digits_from::<u8>("01", 0),
+            "100000000",
+            "failed to parse \"100000000\" as type u8: number out of range for the target type",
+        );
+        assert_parse_eq(digits_from::<u8>("ab", 0), "bba", 6);
+    }
+
+    #[test]
+    #[should_panic(expected = "radix must be in the range 2 to 36")]
+    fn test_int_radix_bad_radix() {
+        int_radix::<u64>(37);
+    }
+
+    #[test]
+    fn test_rational() {
+        assert_parse_eq(rational, "1/4", Ratio::new(1, 4));
+        assert_parse_eq(rational, "-6/4", Ratio::new(-3, 2));
+        assert_parse_eq(rational, "+7", Ratio::from_integer(7));
+        assert_no_parse(rational, "1/-4");
+        assert_parse_eq(sequence(rational, "/"), "1/", Ratio::from_integer(1));
+        assert_parse_error(
+            rational,
+            "1/0",
+            r#"failed to parse "1/0" as type Ratio<i64>: zero value denominator"#,
+        );
+        assert_parse_error(rational, "x", "expected a fraction at line 1 column 1");
+
+        let big = "123456789012345678901234567890";
+        let expected = BigRational::new(big.parse().unwrap(), 2.into());
+        assert_parse_eq(big_rational, &format!("{big}/2"), expected);
+    }
+
+    #[test]
+    fn test_decimal() {
+        assert_parse_eq(decimal, "3.25", Ratio::new(13, 4));
+        assert_parse_eq(decimal, "-0.1", Ratio::new(-1, 10));
+        assert_parse_eq(decimal, "42", Ratio::from_integer(42));
+        assert_parse_eq(decimal, "1.5e3", Ratio::from_integer(1500));
+        assert_parse_eq(decimal, "25E-4", Ratio::new(1, 400));
+        assert_parse_eq(decimal, "+0.000", Ratio::from_integer(0));
+        assert_parse_eq(sequence(decimal, ".x"), "3.x", Ratio::from_integer(3));
+        assert_no_parse(decimal, ".5");
+        assert_parse_error(decimal, "1e19", "number too large to fit in target type");
+        assert_parse_error(decimal, "0.1e-19", "number too large to fit in target type");
+        assert_parse_error(decimal, "1e99999", "exponent too large");
+
+        // Trailing zeros and common factors don't count against the limit.
+        assert_parse_eq(decimal, "1.0000000000000000000", Ratio::from_integer(1));
+        assert_parse_eq(decimal, "-12.50000000000000000000", Ratio::new(-25, 2));
+        assert_parse_eq(
+            decimal,
+            "100000000000000000000e-2",
+            Ratio::from_integer(10i64.pow(18)),
+        );
+        assert_parse_eq(decimal, "5e-19", Ratio::new(1, 2 * 10i64.pow(18)));
+        assert_parse_eq(decimal, "-0.00000000000000000000", Ratio::from_integer(0));
+        assert_parse_error(decimal, "10000000000000000001", "number too large");
+        assert_parse_error(decimal, "-", "expected a decimal number at");
+
+        assert_parse_eq(
+            big_decimal,
+            "1e30",
+            BigRational::from_integer("1000000000000000000000000000000".parse().unwrap()),
+        );
+        assert_parse_eq(
+            big_decimal,
+            "-12.5e-30",
+            BigRational::new((-125).into(), BigInt::from(10).pow(31)),
+        );
+    }
+
     #[test]
     fn test_bigint() {
         assert_no_parse(big_uint, "");
@@ -286,4 +805,47 @@ mod tests {
         assert_no_parse(f64, "6.022e");
         assert_no_parse(f64, "6.022e+");
     }
+
+    #[test]
+    fn test_int() {
+        assert_parse_eq(u8, "0", 0);
+        assert_parse_eq(u8, "255", u8::MAX);
+        assert_parse_eq(u8, "000000000000000000000255", u8::MAX);
+        assert_no_parse(u8, "+1");
+        assert_no_parse(u8, "-0");
+        assert_parse_eq(i8, "-128", i8::MIN);
+        assert_parse_eq(i8, "+127", i8::MAX);
+        assert_parse_eq(i8, "-0", 0);
+        assert_no_parse(i8, "-129");
+        assert_no_parse(i8, "128");
+        assert_no_parse(i64, "--1");
+        assert_parse_eq(u128, &u128::MAX.to_string(), u128::MAX);
+        assert_parse_eq(i128, &i128::MIN.to_string(), i128::MIN);
+        assert_parse_eq(sequence(i32, i32), "-12+34", (-12, 34));
+        assert_parse_eq(sequence(u64, "x"), "19x", 19);
+        assert_parse_eq(i16_bin, "-1000000000000000", i16::MIN);
+
+        // Overflow errors are the standard library's.
+        assert_parse_error(
+            i8,
+            "-129",
+            "failed to parse \"-129\" as type i8: number too small to fit in target type",
+        );
+        assert_parse_error(
+            u64_hex,
+            "1ffffffffffffffff",
+            "failed to parse \"1ffffffffffffffff\" as type u64: number too large to fit in target type",
+        );
+    }
+
+    #[test]
+    fn test_error_nouns() {
+        assert_parse_error(u32, "x", "expected an unsigned integer at line 1 column 1");
+        assert_parse_error(i64, "-", "expected an integer at");
+        assert_parse_error(u32_hex, "xyz", "expected a hexadecimal u32 at");
+        assert_parse_error(i8_bin, "2", "expected a binary i8 at");
+        assert_parse_error(big_int, "z", "expected an integer at");
+        assert_parse_error(f64, "e", "expected a number at");
+        assert_parse_error(u8, "300", "failed to parse \"300\" as type u8");
+    }
 }
@@ -1,13 +1,13 @@
 //! Parsers using Regex.
 
 use std::{
-    any::{self, Any},
-    fmt::Display,
+    any::Any,
+    fmt::{self, Display},
 };
 
 use regex::Regex;
 
-use crate::{parsers::BasicParseIter, ParseContext, Parser, Reported, Result};
+use crate::{describe, parsers::BasicParseIter, ParseContext, Parser, Reported, Result};
 
 /// This parser matches using a regex, then converts the value to a Rust value
 /// using the given `parse_fn`.
@@ -23,6 +23,8 @@ use crate::{parsers::BasicParseIter, ParseContext, Parser, Reported, Result};
 /// string) because the regex matches all lowercase letters, leaving nothing
 /// for the next pattern `'a'` to match.
 pub struct RegexParser<T, E> {
+    pub(crate) name: &'static str,
+    pub(crate) noun: &'static str,
     pub(crate) regex: fn() -> &'static Regex,
     pub(crate) parse_fn: fn(&str) -> Result<T, E>,
 }
@@ -30,10 +32,7 @@ pub struct RegexParser<T, E> {
 // Manual Clone impl because `#[derive(Clone)]` is buggy in this case.
 impl<T, E> Clone for RegexParser<T, E> {
     fn clone(&self) -> Self {
-        RegexParser {
-            regex: self.regex,
-            parse_fn: self.parse_fn,
-        }
+        *self
     }
 }
 
@@ -46,7 +45,8 @@ where
 {
     type Output = T;
     type RawOutput = (T,);
-    type Iter<'parse> = BasicParseIter<T>
+    type Iter<'parse>
+        = BasicParseIter<T>
     where
         E: 'parse;
 
@@ -56,7 +56,7 @@ where
         start: usize,
     ) -> Result<Self::Iter<'parse>, Reported> {
         match (self.regex)().find(&context.source()[start..]) {
-            None => Err(context.error_expected(start, any::type_name::<T>())),
+            None => Err(context.error_expected(start, self.noun)),
             Some(m) => match (self.parse_fn)(m.as_str()) {
                 Ok(value) => Ok(BasicParseIter {
                     end: start + m.end(),
@@ -65,10 +65,14 @@ where
                 Err(err) => Err(context.error_from_str_failed(
                     start,
                     start + m.end(),
-                    any::type_name::<T>(),
+                    describe::type_name::<T>(),
                     format!("{err}"),
                 )),
             },
         }
     }
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name)
+    }
 }
@@ -1,7 +1,11 @@
 //! Parsing a repeated pattern.
 
+use std::{cell::Cell, fmt};
+
 use crate::{
+    describe,
     parsers::{empty, EmptyParser},
+    trace,
     types::ParserOutput,
     ParseContext, ParseIter, Parser, Reported, Result,
 };
@@ -22,7 +26,18 @@ where
 {
     params: &'parse RepeatParser<Pattern, Sep>,
     start: usize,
+    /// End position of each pattern match so far.
+    pattern_ends: Vec<usize>,
+    /// Iterators for the pattern matches so far, kept only if the pattern can
+    /// backtrack.
     pattern_iters: Vec<Pattern::Iter<'parse>>,
+    /// If the pattern can't backtrack, the value of each match so far,
+    /// converted as soon as it matched. `None` after `convert` takes them.
+    values: Cell<Option<Vec<Pattern::Output>>>,
+    /// End position of each separator match so far.
+    sep_ends: Vec<usize>,
+    /// Iterators for the separator matches so far, kept only if the
+    /// separator can backtrack.
     sep_iters: Vec<Sep::Iter<'parse>>,
 }
 
@@ -33,7 +48,8 @@ where
 {
     type Output = Vec<Pattern::Output>;
     type RawOutput = (Vec<Pattern::Output>,);
-    type Iter<'parse> = RepeatParseIter<'parse, Pattern, Sep>
+    type Iter<'parse>
+        = RepeatParseIter<'parse, Pattern, Sep>
     where
         Pattern: 'parse,
         Sep: 'parse;
@@ -46,12 +62,46 @@ where
         let mut iter = RepeatParseIter {
             params: self,
             start,
+            pattern_ends: vec![],
             pattern_iters: vec![],
+            values: Cell::new(Some(vec![])),
+            sep_ends: vec![],
             sep_iters: vec![],
         };
         iter.next(context, Mode::Advance)?;
         Ok(iter)
     }
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let no_sep = self.sep.is_empty_parser();
+        match (no_sep, self.min, self.max, self.sep_is_terminator) {
+            (true, 0, None, false) => {
+                if let Some(result) = self.pattern.fmt_repeated(f) {
+                    return result;
+                }
+                describe::write_atom(f, &self.pattern)?;
+                f.write_str("*")
+            }
+            (true, 1, None, false) => {
+                describe::write_atom(f, &self.pattern)?;
+                f.write_str("+")
+            }
+            (_, 0, None, false) => {
+                f.write_str("repeat_sep(")?;
+                describe::write_pattern(f, &self.pattern)?;
+                f.write_str(", ")?;
+                describe::write_pattern(f, &self.sep)?;
+                f.write_str(")")
+            }
+            (_, min, max, sep_is_terminator) => {
+                f.write_str("repeat(")?;
+                describe::write_pattern(f, &self.pattern)?;
+                f.write_str(", ")?;
+                describe::write_pattern(f, &self.sep)?;
+                write!(f, ", {min}, {max:?}, {sep_is_terminator})")
+            }
+        }
+    }
 }
 
 impl<Pattern, Sep> RepeatParser<Pattern, Sep> {
@@ -81,12 +131,12 @@ where
     Sep: Parser,
 {
     fn num_matches(&self) -> usize {
-        self.pattern_iters.len() + self.sep_iters.len()
+        self.pattern_ends.len() + self.sep_ends.len()
     }
 
     // True if we've matched as many separators as patterns, so pattern is next.
     fn is_pattern_next(&self) -> bool {
-        self.pattern_iters.len() == self.sep_iters.len()
+        self.pattern_ends.len() == self.sep_ends.len()
     }
 
     /// End position of what's been matched so far.
@@ -94,10 +144,29 @@ where
         if self.num_matches() == 0 {
             self.start
         } else if self.is_pattern_next() {
-            self.sep_iters.last().unwrap().match_end()
+            *self.sep_ends.last().unwrap()
         } else {
-            self.pattern_iters.last().unwrap().match_end()
+            *self.pattern_ends.last().unwrap()
+        }
+    }
+
+    /// `convert` took the values of the pattern matches, and now we need them
+    /// again. Match the pattern again to get them back. It can't backtrack,
+    /// so it matches the same way it did before.
+    fn redo_values(&mut self, context: &ParseContext<'parse>) -> Result<(), Reported> {
+        let mut context = context.detach();
+        let mut values = Vec::with_capacity(self.pattern_ends.len());
+        for i in 0..self.pattern_ends.len() {
+            let start = if i == 0 {
+                self.start
+            } else {
+                self.sep_ends[i - 1]
+            };
+            let iter = self.params.pattern.parse_iter(&mut context, start)?;
+            values.push(iter.convert().into_user_type());
         }
+        self.values.set(Some(values));
+        Ok(())
     }
 
     /// Precondition: Either there are no iters or we just successfully
@@ -106,21 +175,36 @@ where
     /// This never returns success because we keep advancing until we fail to
     /// match, then return the error without trying to backtrack.
     fn advance(&mut self, context: &mut ParseContext<'parse>) -> Result<(), Reported> {
-        // TODO: When considering creating a new iterator, if we have already
-        // matched `max` times, don't bother; no matches can come of it.
         loop {
-            assert_eq!(self.pattern_iters.len(), (self.num_matches() + 1) / 2);
-            assert_eq!(self.sep_iters.len(), self.num_matches() / 2);
+            assert_eq!(self.pattern_ends.len(), self.num_matches().div_ceil(2));
+            assert_eq!(self.sep_ends.len(), self.num_matches() / 2);
 
+            // Once we have matched `max` times, don't look for more; no
+            // matches can come of it, only misleading errors.
             if self.is_pattern_next() {
+                if Some(self.pattern_ends.len()) == self.params.max {
+                    return Err(Reported);
+                }
                 let start = self.end();
-                let iter = self.params.pattern.parse_iter(context, start)?;
-                self.pattern_iters.push(iter);
+                let iter = trace::parse_iter(&self.params.pattern, context, start)?;
+                self.pattern_ends.push(iter.match_end());
+                if Pattern::Iter::is_deterministic() {
+                    let value = iter.convert().into_user_type();
+                    self.values.get_mut().as_mut().unwrap().push(value);
+                } else {
+                    self.pattern_iters.push(iter);
+                }
+            }
+            if !self.params.sep_is_terminator && Some(self.pattern_ends.len()) == self.params.max {
+                return Err(Reported);
             }
 
             let start = self.end();
-            let iter = self.params.sep.parse_iter(context, start)?;
-            self.sep_iters.push(iter);
+            let iter = trace::parse_iter(&self.params.sep, context, start)?;
+            self.sep_ends.push(iter.match_end());
+            if !Sep::Iter::is_deterministic() {
+                self.sep_iters.push(iter);
+            }
         }
     }
 
@@ -130,17 +214,35 @@ where
                 Mode::BacktrackTopIter => {
                     // Need to call backtrack() on the top iter. If that
                     // succeeds, advance again.
-                    assert_eq!(self.pattern_iters.len(), (self.num_matches() + 1) / 2);
-                    assert_eq!(self.sep_iters.len(), self.num_matches() / 2);
+                    assert_eq!(self.pattern_ends.len(), self.num_matches().div_ceil(2));
+                    assert_eq!(self.sep_ends.len(), self.num_matches() / 2);
 
                     if self.num_matches() == 0 {
                         // No more iterators. We exhausted all possibilities.
                         return Err(Reported);
                     }
+                    let params = self.params;
+                    // Don't bother asking iterators that can't backtrack.
                     let backtrack_result = if self.is_pattern_next() {
-                        self.sep_iters.last_mut().unwrap().backtrack(context)
+                        if Sep::Iter::is_deterministic() {
+                            Err(Reported)
+                        } else {
+                            let iter = self.sep_iters.last_mut().unwrap();
+                            let result = trace::backtrack(&params.sep, iter, context);
+                            if result.is_ok() {
+                                *self.sep_ends.last_mut().unwrap() = iter.match_end();
+                            }
+                            result
+                        }
+                    } else if Pattern::Iter::is_deterministic() {
+                        Err(Reported)
                     } else {
-                        self.pattern_iters.last_mut().unwrap().backtrack(context)
+                        let iter = self.pattern_iters.last_mut().unwrap();
+                        let result = trace::backtrack(&params.pattern, iter, context);
+                        if result.is_ok() {
+                            *self.pattern_ends.last_mut().unwrap() = iter.match_end();
+                        }
+                        result
                     };
 
                     mode = match backtrack_result {
@@ -161,13 +263,18 @@ where
                 Mode::Exhausted => {
                     // We just called backtrace() on the top iter, and it
                     // failed. It's exhausted and needs to be discarded.
-                    assert_eq!(self.pattern_iters.len(), (self.num_matches() + 1) / 2);
-                    assert_eq!(self.sep_iters.len(), self.num_matches() / 2);
+                    assert_eq!(self.pattern_ends.len(), self.num_matches().div_ceil(2));
+                    assert_eq!(self.sep_ends.len(), self.num_matches() / 2);
 
                     if self.is_pattern_next() {
+                        self.sep_ends.pop();
                         self.sep_iters.pop();
                     } else {
+                        self.pattern_ends.pop();
                         self.pattern_iters.pop();
+                        if let Some(values) = self.values.get_mut() {
+                            values.pop();
+                        }
                     }
                     mode = Mode::YieldThenBacktrack;
                 }
@@ -203,10 +310,20 @@ where
     }
 
     fn backtrack(&mut self, context: &mut ParseContext<'parse>) -> Result<(), Reported> {
+        if self.values.get_mut().is_none() {
+            self.redo_values(context)?;
+        }
         self.next(context, Mode::BacktrackTopIter)
     }
 
     fn convert(&self) -> (Vec<Pattern::Output>,) {
+        if Pattern::Iter::is_deterministic() {
+            let values = self
+                .values
+                .take()
+                .expect("RepeatParseIter::convert called twice without backtracking");
+            return (values,);
+        }
         let v = self
             .pattern_iters
             .iter()
@@ -255,10 +372,30 @@ pub fn repeat_sep<Pattern, Sep>(pattern: Pattern, sep: Sep) -> RepeatParser<Patt
     repeat(pattern, sep, 0, None, false)
 }
 
+/// <code>repeat_n(<var>pattern</var>, <var>n</var>)</code> matches the
+/// given *pattern* exactly *n* times, producing a `Vec` of length *n*.
+///
+/// This is most useful when *n* comes from earlier in the input; see
+/// [`then()`][crate::prelude::then].
+pub fn repeat_n<Pattern>(pattern: Pattern, n: usize) -> RepeatParser<Pattern, EmptyParser> {
+    repeat(pattern, empty(), n, Some(n), false)
+}
+
+/// <code>repeat_sep_n(<var>pattern</var>, <var>separator</var>,
+/// <var>n</var>)</code> matches the given *pattern* exactly *n* times,
+/// separated by the *separator*.
+pub fn repeat_sep_n<Pattern, Sep>(
+    pattern: Pattern,
+    sep: Sep,
+    n: usize,
+) -> RepeatParser<Pattern, Sep> {
+    repeat(pattern, sep, n, Some(n), false)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::parsers::usize;
+    use crate::parsers::{alpha, alt, usize};
     use crate::testing::*;
 
     #[test]
@@ -291,4 +428,78 @@ mod tests {
         let p = repeat_sep(usize, ",");
         assert_parse_eq(p, "11417,0,0,334", vec![11417usize, 0, 0, 334]);
     }
+
+    #[test]
+    fn test_repeat_backtracking() {
+        fn is_deterministic<P: Parser>(_parser: &P) -> bool {
+            P::Iter::is_deterministic()
+        }
+
+        // Deterministic patterns and separators are never asked to
+        // backtrack; the repeat just drops their matches.
+        assert!(is_deterministic(&sequence(usize, ',')));
+        assert!(!is_deterministic(&star(usize)));
+        let p = sequence(star(sequence(usize, ',')), sequence(usize, ",,"));
+        assert_parse_eq(p, "1,2,3,,", (vec![1, 2], 3));
+
+        // A repeat of a deterministic pattern keeps only the converted values
+        // and end offsets, not the iterators.
+        let p = repeat_sep(usize, ",");
+        let mut context = ParseContext::new("1,2,3");
+        let Ok(iter) = p.parse_iter(&mut context, 0) else {
+            panic!("no match");
+        };
+        assert!(iter.pattern_iters.is_empty());
+        assert!(iter.sep_iters.is_empty());
+        assert_eq!(iter.sep_ends, vec![2, 4]);
+        assert_eq!(iter.convert(), (vec![1, 2, 3],));
+
+        // Converting hands the values off; backtracking afterwards rebuilds
+        // the ones that are left.
+        let seen = std::cell::RefCell::new(vec![]);
+        let p = crate::parsers::then(star(sequence(usize, ',')), |v: Vec<usize>| {
+            let n = if v.len() == 3 { 5 } else { 1 };
+            seen.borrow_mut().push(v);
+            repeat_n(sequence(usize, ','), n)
+        });
+        assert_parse_eq(&p, "1,2,3,", vec![3]);
+        assert_eq!(*seen.borrow(), vec![vec![1, 2, 3], vec![1, 2]]);
+
+        // Separators that can backtrack are still backtracked.
+        let p = sequence(repeat_sep("a", alt(",", ",,")), ",b");
+        assert_parse_eq(p, "a,,a,b", vec![(), ()]);
+        assert_parse_eq(p, "a,b", vec![()]);
+        assert_no_parse(p, "a,,,b");
+    }
+
+    #[test]
+    fn test_repeat_errors() {
+        // Without a maximum, the error is wherever the pattern stopped
+        // matching.
+        assert_parse_error(star("a"), "aab", "expected \"a\" at line 1 column 3");
+        assert_parse_error(plus("a"), "b", "expected \"a\" at line 1 column 1");
+        assert_parse_error(
+            repeat_sep(usize, ","),
+            "1,2,x",
+            "expected an unsigned integer at line 1 column 5",
+        );
+
+        // With a maximum, the repeat doesn't look past it, so it doesn't
+        // complain about a missing match that couldn't be used anyway.
+        assert_parse_error(
+            repeat_n(alpha, 2),
+            "abc",
+            "extra unparsed text after match at line 1 column 3",
+        );
+        assert_parse_error(
+            repeat_sep_n(usize, ",", 2),
+            "1,2,",
+            "extra unparsed text after match at line 1 column 4",
+        );
+        assert_parse_error(
+            repeat_n(alpha, 3),
+            "ab",
+            "expected a letter at end of input",
+        );
+    }
 }
@@ -1,7 +1,7 @@
-use std::{any::Any, marker::PhantomData, pin::Pin};
+use std::{any::Any, fmt, marker::PhantomData, pin::Pin};
 
 use crate::{
-    parsers::dynamic::{DynParseIter, DynParser},
+    parsers::dynamic::{BoxedParseIter, BoxedParser, Local},
     ParseContext, Parser, Reported,
 };
 
@@ -14,10 +14,14 @@ use crate::{
 /// -   Then `.assign_parser_for_rule()` the same number of times, in the same order,
 ///     to assign the actual parsers implementing each rule.
 /// -   Lastly `.build()` to build the entry point to the rule set.
-pub struct RuleSetBuilder {
+///
+/// `M` is the types of the parsers assigned so far, as nested pairs `(((),
+/// P1), P2)`. See `RuleSetParser`.
+pub struct RuleSetBuilder<M = ()> {
     id: Pin<Box<u8>>,
     capacity: usize,
     rule_parsers: Vec<Box<dyn Any>>,
+    parser_types: PhantomData<fn() -> M>,
 }
 
 /// Parser for a rule in a rule set.
@@ -26,17 +30,58 @@ pub struct RuleSetBuilder {
 #[doc(hidden)]
 #[derive(Debug)]
 pub struct RuleParser<T> {
+    name: &'static str,
     rule_set_id: usize,
     index: usize,
     phantom: PhantomData<fn() -> T>,
 }
 
-pub struct RuleSetParser<T> {
+/// The type of parser produced by `parser!` when it has `rule`s.
+///
+/// `M` is the types of all the patterns in the rule set. A `RuleSetParser` is
+/// `Send` and `Sync` when all of them are, so it can be shared across threads
+/// or built once and stored in a `static`. The types of patterns can't be
+/// written down, so to store one, [box it][crate::Parser::boxed]:
+///
+/// ```
+/// # use std::sync::LazyLock;
+/// # use aoc_parse::{parser, prelude::*, BoxedParser};
+/// static SUM: LazyLock<BoxedParser<u64>> = LazyLock::new(|| {
+///     parser!(
+///         rule term: u64 = {u64, '(' s:sum ')' => s};
+///         rule sum: u64 = ts:repeat_sep(term, '+') => ts.into_iter().sum();
+///         sum
+///     )
+///     .boxed()
+/// });
+///
+/// assert_eq!(SUM.parse("1+(2+3)").unwrap(), 6);
+/// ```
+///
+/// A rule set whose patterns capture something that can't be shared still
+/// works, but only on the thread that built it:
+///
+/// ```compile_fail
+/// # use std::rc::Rc;
+/// # use aoc_parse::{parser, prelude::*};
+/// let scale = Rc::new(10);
+/// let p = parser!(rule n: u64 = x:u64 => x * *scale; n);
+/// std::thread::spawn(move || p.parse("4"));
+/// //                 ^ERROR: `Rc<u64>` cannot be shared between threads safely
+/// ```
+pub struct RuleSetParser<T, M> {
     id: Pin<Box<u8>>,
     rule_parsers: Vec<Box<dyn Any>>,
-    entry_parser: DynParser<'static, T>,
+    entry_parser: BoxedParser<'static, T, Local>,
+    parser_types: PhantomData<fn() -> M>,
 }
 
+// SAFETY: The boxes in `rule_parsers` and `entry_parser` hold parsers of the
+// types in `M`, as `RuleSetBuilder` ensures, and nothing else. It's safe to
+// send or share them across threads if it's safe to do so with those parsers.
+unsafe impl<T, M: Send + Sync> Send for RuleSetParser<T, M> {}
+unsafe impl<T, M: Send + Sync> Sync for RuleSetParser<T, M> {}
+
 impl RuleSetBuilder {
     /// Create a builder to build a new parser based on a rule set.
     ///
@@ -47,17 +92,22 @@ impl RuleSetBuilder {
             id: Box::pin(0),
             capacity: 0,
             rule_parsers: vec![],
+            parser_types: PhantomData,
         }
     }
+}
 
+impl<M> RuleSetBuilder<M> {
     /// Create a `Copy` parser as a placeholder for a rule in a rule set.
+    /// `name` is used only to describe the parser.
     ///
     /// This is used by the `parser!` macro to implement `rule`.
     #[doc(hidden)]
-    pub fn new_rule<T>(&mut self) -> RuleParser<T> {
+    pub fn new_rule<T>(&mut self, name: &'static str) -> RuleParser<T> {
         let index = self.capacity;
         self.capacity += 1;
         RuleParser {
+            name,
             rule_set_id: self.id(),
             index,
             phantom: PhantomData,
@@ -68,7 +118,11 @@ impl RuleSetBuilder {
     ///
     /// This is used by the `parser!` macro to implement `rule`.
     #[doc(hidden)]
-    pub fn assign_parser_for_rule<T, P>(&mut self, nt: &RuleParser<T>, parser: P)
+    pub fn assign_parser_for_rule<T, P>(
+        mut self,
+        nt: &RuleParser<T>,
+        parser: P,
+    ) -> RuleSetBuilder<(M, P)>
     where
         T: 'static,
         P: Parser<Output = T> + 'static,
@@ -77,21 +131,29 @@ impl RuleSetBuilder {
         assert_eq!(nt.index, self.rule_parsers.len());
 
         // We are double-boxing the parsers at the moment. Look away
-        self.rule_parsers.push(Box::new(DynParser::new(parser)));
+        self.rule_parsers
+            .push(Box::new(BoxedParser::new_local(parser)));
+        RuleSetBuilder {
+            id: self.id,
+            capacity: self.capacity,
+            rule_parsers: self.rule_parsers,
+            parser_types: PhantomData,
+        }
     }
 
     /// Build the rule-set-based parser.
     ///
     /// This is used by the `parser!` macro to implement `rule`.
     #[doc(hidden)]
-    pub fn build<P>(self, parser: P) -> RuleSetParser<P::Output>
+    pub fn build<P>(self, parser: P) -> RuleSetParser<P::Output, (M, P)>
     where
         P: Parser + 'static,
     {
         RuleSetParser {
             id: self.id,
             rule_parsers: self.rule_parsers,
-            entry_parser: DynParser::new(parser),
+            entry_parser: BoxedParser::new_local(parser),
+            parser_types: PhantomData,
         }
     }
 
@@ -104,11 +166,7 @@ impl RuleSetBuilder {
 // case.
 impl<T> Clone for RuleParser<T> {
     fn clone(&self) -> Self {
-        RuleParser {
-            rule_set_id: self.rule_set_id,
-            index: self.index,
-            phantom: self.phantom,
-        }
+        *self
     }
 }
 
@@ -120,7 +178,10 @@ where
 {
     type Output = T;
     type RawOutput = (T,);
-    type Iter<'parse> = DynParseIter<'parse, T> where T: 'parse;
+    type Iter<'parse>
+        = BoxedParseIter<'parse, T>
+    where
+        T: 'parse;
 
     fn parse_iter<'parse>(
         &'parse self,
@@ -130,22 +191,31 @@ where
         let parser_as_any: &'parse dyn Any =
             context.fetch_parser_for_rule(self.rule_set_id, self.index);
         let parser = parser_as_any
-            .downcast_ref::<DynParser<T>>()
+            .downcast_ref::<BoxedParser<T, Local>>()
             .expect("internal error: downcast failed");
         parser.parse_iter(context, start)
     }
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Describing the rule's pattern here could recurse forever.
+        f.write_str(self.name)
+    }
 }
 
-impl<T> RuleSetParser<T> {
+impl<T, M> RuleSetParser<T, M> {
     fn id(&self) -> usize {
         &self.id as &u8 as *const u8 as usize
     }
 }
 
-impl<T> Parser for RuleSetParser<T> {
+impl<T, M> Parser for RuleSetParser<T, M> {
     type Output = T;
     type RawOutput = (T,);
-    type Iter<'parse> = DynParseIter<'parse, T> where T: 'parse;
+    type Iter<'parse>
+        = BoxedParseIter<'parse, T>
+    where
+        T: 'parse,
+        M: 'parse;
 
     fn parse_iter<'parse>(
         &'parse self,
@@ -155,6 +225,10 @@ impl<T> Parser for RuleSetParser<T> {
         context.register_rule_set(self.id(), &self.rule_parsers);
         self.entry_parser.parse_iter(context, start)
     }
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.entry_parser.fmt_pattern(f)
+    }
 }
 
 #[cfg(test)]
@@ -173,10 +247,10 @@ mod tests {
 
         let value_parser = {
             let mut builder = RuleSetBuilder::new();
-            let value: RuleParser<Value> = builder.new_rule();
-            let values: RuleParser<Vec<Value>> = builder.new_rule();
+            let value: RuleParser<Value> = builder.new_rule("value");
+            let values: RuleParser<Vec<Value>> = builder.new_rule("values");
 
-            builder.assign_parser_for_rule(
+            let builder = builder.assign_parser_for_rule(
                 &value,
                 alt(
                     map(u32, Value::Int),
@@ -186,11 +260,22 @@ mod tests {
                     ),
                 ),
             );
-            builder.assign_parser_for_rule(&values, repeat_sep(value, ","));
+            let builder = builder.assign_parser_for_rule(&values, repeat_sep(value, ","));
             builder.build(value)
         };
 
+        assert_eq!(value_parser.describe().to_string(), "value");
+
         assert_parse_eq(&value_parser, "92183", Value::Int(92183));
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                assert_parse_eq(
+                    &value_parser,
+                    "[[]]",
+                    Value::List(vec![Value::List(vec![])]),
+                )
+            });
+        });
         assert_parse_eq(
             &value_parser,
             "[3,[7,88]]",
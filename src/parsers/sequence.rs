@@ -1,6 +1,9 @@
 //! Matching patterns in sequence.
 
+use std::fmt;
+
 use crate::{
+    describe, trace,
     types::{ParserOutput, RawOutputConcat},
     ParseContext, ParseIter, Parser, Reported, Result,
 };
@@ -68,7 +71,8 @@ where
 {
     type Output = <Op::Output as ParserOutput>::UserType;
     type RawOutput = Op::Output;
-    type Iter<'parse> = SequenceParseIter<'parse, Head, Tail, Op>
+    type Iter<'parse>
+        = SequenceParseIter<'parse, Head, Tail, Op>
     where
         Head: 'parse,
         Tail: 'parse,
@@ -79,19 +83,35 @@ where
         context: &mut ParseContext<'parse>,
         start: usize,
     ) -> Result<Self::Iter<'parse>, Reported> {
-        let mut head_iter = self.head.parse_iter(context, start)?;
-        let tail_iter = first_tail_match::<Head, Tail>(context, &mut head_iter, &self.tail)?;
+        let mut head_iter = trace::parse_iter(&self.head, context, start)?;
+        let tail_iter =
+            first_tail_match::<Head, Tail>(context, &self.head, &mut head_iter, &self.tail)?;
         Ok(SequenceParseIter {
             parsers: self,
             head_iter,
             tail_iter,
         })
     }
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parenthesize = describe::is_atom_position(f);
+        if parenthesize {
+            f.write_str("(")?;
+        }
+        describe::write_pattern(f, &self.head)?;
+        f.write_str(" ")?;
+        describe::write_pattern(f, &self.tail)?;
+        if parenthesize {
+            f.write_str(")")?;
+        }
+        Ok(())
+    }
 }
 
 fn first_tail_match<'parse, Head, Tail>(
     context: &mut ParseContext<'parse>,
-    head: &mut Head::Iter<'parse>,
+    head: &Head,
+    head_iter: &mut Head::Iter<'parse>,
     tail: &'parse Tail,
 ) -> Result<Tail::Iter<'parse>, Reported>
 where
@@ -99,11 +119,11 @@ where
     Tail: Parser,
 {
     loop {
-        let mid = head.match_end();
-        if let Ok(tail_iter) = tail.parse_iter(context, mid) {
+        let mid = head_iter.match_end();
+        if let Ok(tail_iter) = trace::parse_iter(tail, context, mid) {
             return Ok(tail_iter);
         }
-        head.backtrack(context)?;
+        trace::backtrack(head, head_iter, context)?;
     }
 }
 
@@ -116,15 +136,24 @@ where
 {
     type RawOutput = Op::Output;
 
+    fn is_deterministic() -> bool {
+        Head::Iter::is_deterministic() && Tail::Iter::is_deterministic()
+    }
+
     fn match_end(&self) -> usize {
         self.tail_iter.match_end()
     }
 
     fn backtrack(&mut self, context: &mut ParseContext<'parse>) -> Result<(), Reported> {
-        self.tail_iter.backtrack(context).or_else(|Reported| {
-            self.head_iter.backtrack(context)?;
-            let tail_iter =
-                first_tail_match::<Head, Tail>(context, &mut self.head_iter, &self.parsers.tail)?;
+        let parsers = self.parsers;
+        trace::backtrack(&parsers.tail, &mut self.tail_iter, context).or_else(|Reported| {
+            trace::backtrack(&parsers.head, &mut self.head_iter, context)?;
+            let tail_iter = first_tail_match::<Head, Tail>(
+                context,
+                &parsers.head,
+                &mut self.head_iter,
+                &parsers.tail,
+            )?;
             self.tail_iter = tail_iter;
             Ok(())
         })
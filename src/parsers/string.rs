@@ -1,6 +1,15 @@
-//! The parser used by the `string()` function.
+//! The parsers used by the `string()` and `str_slice()` functions.
 
-use crate::{ParseContext, ParseIter, Parser, Reported, Result};
+use std::{
+    borrow::Borrow,
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    ops::{Deref, Range},
+    sync::Arc,
+};
+
+use crate::{describe, ParseContext, ParseIter, Parser, Reported, Result};
 
 #[derive(Clone, Copy)]
 pub struct StringParser<P> {
@@ -22,7 +31,8 @@ where
 {
     type Output = String;
     type RawOutput = (String,);
-    type Iter<'parse> = StringParseIter<'parse, P>
+    type Iter<'parse>
+        = StringParseIter<'parse, P>
     where
         P: 'parse;
 
@@ -38,6 +48,12 @@ where
             iter,
         })
     }
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("string(")?;
+        describe::write_pattern(f, &self.parser)?;
+        f.write_str(")")
+    }
 }
 
 impl<'parse, P> ParseIter<'parse> for StringParseIter<'parse, P>
@@ -46,6 +62,10 @@ where
 {
     type RawOutput = (String,);
 
+    fn is_deterministic() -> bool {
+        P::Iter::is_deterministic()
+    }
+
     fn match_end(&self) -> usize {
         self.iter.match_end()
     }
@@ -60,3 +80,228 @@ where
         (value,)
     }
 }
+
+/// A slice of the input, produced by the `str_slice()` pattern.
+///
+/// This derefs to `str`. It works as a `HashMap` key that can be looked up
+/// with a plain `&str`, and it compares, hashes, and prints just like the
+/// text it contains.
+///
+/// Making one doesn't copy any text. Instead, the whole input is copied into
+/// a reference-counted buffer once per parse, and every `StrSlice` shares it.
+/// To avoid even that copy, put the input in an `Arc<str>` and use
+/// [`Parser::parse_shared`].
+///
+/// A plain `&str` borrowed from the input would be nicer, but
+/// [`Parser::Output`] can't depend on the lifetime of the input.
+#[derive(Clone)]
+pub struct StrSlice {
+    input: Arc<str>,
+    start: usize,
+    end: usize,
+}
+
+impl StrSlice {
+    /// The text of this slice.
+    pub fn as_str(&self) -> &str {
+        &self.input[self.start..self.end]
+    }
+
+    /// The byte offsets of this slice within the string that was parsed.
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+}
+
+impl Deref for StrSlice {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for StrSlice {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Borrow<str> for StrSlice {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl PartialEq for StrSlice {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for StrSlice {}
+
+impl PartialEq<str> for StrSlice {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for StrSlice {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialOrd for StrSlice {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for StrSlice {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl Hash for StrSlice {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl fmt::Debug for StrSlice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for StrSlice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+/// The type of parser returned by `str_slice()`.
+#[derive(Clone, Copy)]
+pub struct StrSliceParser<P> {
+    pub(crate) parser: P,
+}
+
+pub struct StrSliceParseIter<'parse, P>
+where
+    P: Parser + 'parse,
+{
+    input: Arc<str>,
+    /// Offset of the context's source within `input`.
+    base: usize,
+    start: usize,
+    iter: P::Iter<'parse>,
+}
+
+impl<P> Parser for StrSliceParser<P>
+where
+    P: Parser,
+{
+    type Output = StrSlice;
+    type RawOutput = (StrSlice,);
+    type Iter<'parse>
+        = StrSliceParseIter<'parse, P>
+    where
+        P: 'parse;
+
+    fn parse_iter<'parse>(
+        &'parse self,
+        context: &mut ParseContext<'parse>,
+        start: usize,
+    ) -> Result<Self::Iter<'parse>, Reported> {
+        let iter = self.parser.parse_iter(context, start)?;
+        let (input, base) = context.shared_input();
+        Ok(StrSliceParseIter {
+            input,
+            base,
+            start,
+            iter,
+        })
+    }
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("str_slice(")?;
+        describe::write_pattern(f, &self.parser)?;
+        f.write_str(")")
+    }
+}
+
+impl<'parse, P> ParseIter<'parse> for StrSliceParseIter<'parse, P>
+where
+    P: Parser,
+{
+    type RawOutput = (StrSlice,);
+
+    fn is_deterministic() -> bool {
+        P::Iter::is_deterministic()
+    }
+
+    fn match_end(&self) -> usize {
+        self.iter.match_end()
+    }
+
+    fn backtrack(&mut self, context: &mut ParseContext<'parse>) -> Result<(), Reported> {
+        self.iter.backtrack(context)
+    }
+
+    fn convert(&self) -> (StrSlice,) {
+        let value = StrSlice {
+            input: self.input.clone(),
+            start: self.base + self.start,
+            end: self.base + self.iter.match_end(),
+        };
+        (value,)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
+    use crate::parsers::{alpha, line, lines, map, section, sections, then, u32};
+    use crate::prelude::str_slice;
+    use crate::testing::*;
+
+    #[test]
+    fn test_str_slice() {
+        let p = str_slice(plus(alpha));
+        assert_parse_eq(p, "hello", "hello");
+        assert_no_parse(p, "");
+
+        // Slices taken inside lines have offsets in the whole input.
+        let p = lines(sequence(str_slice(plus(alpha)), sequence("=", u32)));
+        let pairs = p.parse("ab=1\ncde=2\n").unwrap();
+        assert_eq!(pairs[1].0, "cde");
+        assert_eq!(pairs[1].0.range(), 5..8);
+
+        let map: HashMap<_, _> = pairs.into_iter().collect();
+        assert_eq!(map.get("cde"), Some(&2));
+    }
+
+    #[test]
+    fn test_str_slice_shares_input() {
+        // Slices made in a detached context inside `then()`, or inside
+        // `section()`, share one copy of the input.
+        let word = str_slice(plus(alpha));
+        let p = then(section(line(word)), |first| {
+            map(sections(line(word)), move |rest| (first.clone(), rest))
+        });
+        let (first, rest) = p.parse("ab\n\ncd\n\nef\n").unwrap();
+        assert_eq!(rest, ["cd", "ef"]);
+        assert!(Arc::ptr_eq(&first.input, &rest[0].input));
+        assert!(Arc::ptr_eq(&rest[0].input, &rest[1].input));
+
+        // With `parse_shared`, that copy is the caller's.
+        let input: Arc<str> = Arc::from("ab\n\ncd\n");
+        let (first, rest) = p.parse_shared(&input).unwrap();
+        assert!(Arc::ptr_eq(&first.input, &input));
+        assert!(Arc::ptr_eq(&rest[0].input, &input));
+    }
+}
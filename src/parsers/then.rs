@@ -0,0 +1,281 @@
+//! Data-dependent parsing: `then(p, f)` matches `p`, then uses its value to
+//! decide what to match next.
+
+use std::{fmt, marker::PhantomData, rc::Rc};
+
+use crate::{
+    describe, trace, types::ParserOutput, ParseContext, ParseIter, Parser, Reported, Result,
+};
+
+/// The type of parser returned by [`then()`].
+///
+/// `Q` is the type of parser `F` returns. It's a type parameter so that
+/// `ThenParser<P, F, Q>: 'parse` implies `Q: 'parse`.
+pub struct ThenParser<P, F, Q> {
+    first: P,
+    f: F,
+    second_type: PhantomData<fn() -> Q>,
+}
+
+// Explicit impls because `derive` would require `Q: Clone`.
+impl<P: Clone, F: Clone, Q> Clone for ThenParser<P, F, Q> {
+    fn clone(&self) -> Self {
+        ThenParser {
+            first: self.first.clone(),
+            f: self.f.clone(),
+            second_type: PhantomData,
+        }
+    }
+}
+
+impl<P: Copy, F: Copy, Q> Copy for ThenParser<P, F, Q> {}
+
+pub struct ThenParseIter<'parse, P, F, Q>
+where
+    P: Parser + 'parse,
+    Q: Parser + 'parse,
+{
+    parser: &'parse ThenParser<P, F, Q>,
+    first_iter: P::Iter<'parse>,
+    second: SecondMatch<'parse, Q>,
+}
+
+/// The parser `f` returned for the current match of `first`, along with an
+/// iterator over its matches.
+struct SecondMatch<'parse, Q>
+where
+    Q: Parser + 'parse,
+{
+    // Fields are dropped in declaration order. `iter` and `context` may hold
+    // references into `*_parser`, so they must come first.
+    iter: Q::Iter<'parse>,
+    /// A context for matching `parser`. The real context can't be used for
+    /// that, because it would keep references into `parser` (rule sets, for
+    /// example) after `parser` is dropped.
+    context: ParseContext<'parse>,
+    /// Never read; this just owns the parser. An `Rc` rather than a `Box`
+    /// because moving a `Box` asserts that nothing else points into it.
+    _parser: Rc<Q>,
+}
+
+impl<P, F, Q> Parser for ThenParser<P, F, Q>
+where
+    P: Parser,
+    F: Fn(P::Output) -> Q,
+    Q: Parser,
+{
+    type Output = Q::Output;
+    type RawOutput = Q::RawOutput;
+    type Iter<'parse>
+        = ThenParseIter<'parse, P, F, Q>
+    where
+        P: 'parse,
+        F: 'parse,
+        Q: 'parse;
+
+    fn parse_iter<'parse>(
+        &'parse self,
+        context: &mut ParseContext<'parse>,
+        start: usize,
+    ) -> Result<Self::Iter<'parse>, Reported> {
+        let mut first_iter = trace::parse_iter(&self.first, context, start)?;
+        let second = self.match_second(context, &mut first_iter)?;
+        Ok(ThenParseIter {
+            parser: self,
+            first_iter,
+            second,
+        })
+    }
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // We can't describe the second parser without running the first.
+        f.write_str("then(")?;
+        describe::write_pattern(f, &self.first)?;
+        f.write_str(", ...)")
+    }
+}
+
+impl<P, F, Q> ThenParser<P, F, Q>
+where
+    P: Parser,
+    F: Fn(P::Output) -> Q,
+    Q: Parser,
+{
+    /// Pass the value of `first_iter`'s current match to `f` and match the
+    /// parser it returns. If that fails, backtrack `first_iter` and try again.
+    fn match_second<'parse>(
+        &'parse self,
+        context: &mut ParseContext<'parse>,
+        first_iter: &mut P::Iter<'parse>,
+    ) -> Result<SecondMatch<'parse, Q>, Reported> {
+        loop {
+            let second = (self.f)(first_iter.convert().into_user_type());
+            if let Ok(second) = SecondMatch::new(second, context, first_iter.match_end()) {
+                return Ok(second);
+            }
+            trace::backtrack(&self.first, first_iter, context)?;
+        }
+    }
+}
+
+impl<'parse, Q> SecondMatch<'parse, Q>
+where
+    Q: Parser + 'parse,
+{
+    fn new(parser: Q, context: &mut ParseContext<'parse>, start: usize) -> Result<Self, Reported> {
+        let parser = Rc::new(parser);
+        // SAFETY: The only things that can hold on to this reference are the
+        // iterator and the detached context, and `SecondMatch` drops both
+        // before `parser`. The `Rc` allocation never moves.
+        let parser_ref: &'parse Q = unsafe { &*Rc::as_ptr(&parser) };
+        let mut detached = context.detach();
+        let result = parser_ref.parse_iter(&mut detached, start);
+        if let Some(err) = detached.take_error() {
+            context.report(err);
+        }
+        Ok(SecondMatch {
+            iter: result?,
+            context: detached,
+            _parser: parser,
+        })
+    }
+
+    fn backtrack(&mut self, context: &mut ParseContext<'parse>) -> Result<(), Reported> {
+        let result = self.iter.backtrack(&mut self.context);
+        if let Some(err) = self.context.take_error() {
+            context.report(err);
+        }
+        result
+    }
+}
+
+impl<'parse, P, F, Q> ParseIter<'parse> for ThenParseIter<'parse, P, F, Q>
+where
+    P: Parser,
+    F: Fn(P::Output) -> Q,
+    Q: Parser,
+{
+    type RawOutput = Q::RawOutput;
+
+    fn match_end(&self) -> usize {
+        self.second.iter.match_end()
+    }
+
+    fn backtrack(&mut self, context: &mut ParseContext<'parse>) -> Result<(), Reported> {
+        if self.second.backtrack(context).is_ok() {
+            return Ok(());
+        }
+        trace::backtrack(&self.parser.first, &mut self.first_iter, context)?;
+        self.second = self.parser.match_second(context, &mut self.first_iter)?;
+        Ok(())
+    }
+
+    fn convert(&self) -> Q::RawOutput {
+        self.second.iter.convert()
+    }
+}
+
+/// <code>then(<var>pattern</var>, <var>f</var>)</code> matches *pattern*,
+/// then passes its value to the closure *f*, which returns the parser for
+/// the text that follows. The result is the value of that second parser.
+///
+/// This is for formats where something early in the input says how to parse
+/// the rest, like a count followed by that many items. The `parser!` syntax
+/// for this is `=>>`:
+///
+/// ```
+/// # use aoc_parse::{parser, prelude::*};
+/// let p = parser!(n:usize ": " =>> repeat_sep_n(alpha, " ", n));
+/// assert_eq!(p.parse("3: a b c").unwrap(), vec!['a', 'b', 'c']);
+/// assert!(p.parse("2: a b c").is_err());
+/// ```
+///
+/// Unlike `=>`, *f* runs while matching, every time *pattern* matches. If
+/// the second parser fails to match, `then` backtracks into *pattern*, and
+/// calls *f* again for each other way *pattern* can match.
+pub fn then<P, F, Q>(pattern: P, f: F) -> ThenParser<P, F, Q>
+where
+    P: Parser,
+    F: Fn(P::Output) -> Q,
+    Q: Parser,
+{
+    ThenParser {
+        first: pattern,
+        f,
+        second_type: PhantomData,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::{alpha, digit, lines, one_of_str, plus, repeat_n, star, u32, usize};
+    use crate::testing::*;
+
+    #[test]
+    fn test_then() {
+        let p = then(sequence(usize, " "), |n| repeat_n(alpha, n));
+        assert_parse_eq(&p, "2 ab", vec!['a', 'b']);
+        assert_parse_eq(&p, "0 ", vec![]);
+        assert_parse_error(&p, "3 ab", "expected a letter at end of input");
+        assert_parse_error(
+            &p,
+            "1 ab",
+            "extra unparsed text after match at line 1 column 4",
+        );
+        assert_eq!(p.describe().to_string(), r#"then(usize " ", ...)"#);
+
+        // Backtracks into the first parser.
+        let p = then(plus(digit), |digits| repeat_n(digit, digits.len()));
+        assert_parse_eq(p, "1234", vec![3, 4]);
+        assert_parse_eq(p, "123456", vec![4, 5, 6]);
+        assert_parse_error(p, "123", "expected a decimal digit at end");
+
+        // Backtracks into the second parser.
+        let p = sequence(then(u32, |n| plus(repeat_n("a", n as usize))), "aaa!");
+        assert_parse_eq(&p, "2aaaaaaa!", vec![vec![(), ()], vec![(), ()]]);
+    }
+
+    #[test]
+    fn test_then_keeps_second_parser() {
+        // `f` runs once per match of the first parser, no matter how often
+        // the second one backtracks.
+        let calls = std::cell::Cell::new(0);
+        let p = sequence(
+            then(plus(digit), |digits| {
+                calls.set(calls.get() + 1);
+                star(repeat_n("a", digits.len()))
+            }),
+            "a!",
+        );
+        assert_parse_eq(&p, "1aaaaaaaa!", vec![vec![()]; 7]);
+        assert_eq!(calls.get(), 1);
+
+        // ...and again each time the first parser backtracks.
+        let calls = std::cell::Cell::new(0);
+        let p = sequence(
+            then(plus("a"), |a| {
+                calls.set(calls.get() + 1);
+                star(repeat_n("a", a.len()))
+            }),
+            "a!",
+        );
+        assert_parse_eq(&p, "aaaaa!", Vec::<Vec<()>>::new());
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[test]
+    fn test_then_vocabulary() {
+        let towels = then(sequence(lines(plus(alpha)), "\n"), |words| {
+            lines(plus(one_of_str(
+                words.into_iter().map(String::from_iter).collect::<Vec<_>>(),
+            )))
+        });
+        assert_parse_eq(
+            &towels,
+            "r\nwr\nb\n\nbrwrr\nrb\n",
+            vec![vec![2, 0, 1, 0], vec![0, 2]],
+        );
+        assert_parse_error(&towels, "r\nb\n\nrbw\n", "at line 4 column 3");
+    }
+}
@@ -0,0 +1,206 @@
+//! Parsing input a line at a time, producing an iterator rather than a `Vec`.
+
+use std::io::BufRead;
+
+use crate::{
+    parsers::{match_fully, Line, Region},
+    types::ParserOutput,
+    ParseContext, ParseError, ParseIter, Parser,
+};
+
+/// An iterator that parses the lines of a string one at a time. Returned by
+/// [`Parser::parse_iter_lines`].
+pub struct IterLines<'parse, P: ?Sized> {
+    parser: &'parse P,
+    /// The context, or `None` once we're done.
+    context: Option<ParseContext<'parse>>,
+    pos: usize,
+}
+
+impl<'parse, P: ?Sized> IterLines<'parse, P> {
+    pub(crate) fn new(parser: &'parse P, source: &'parse str) -> Self {
+        IterLines {
+            parser,
+            context: Some(ParseContext::new(source)),
+            pos: 0,
+        }
+    }
+}
+
+impl<'parse, P> Iterator for IterLines<'parse, P>
+where
+    P: Parser + ?Sized,
+{
+    type Item = Result<P::Output, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let context = self.context.as_mut()?;
+        let start = self.pos;
+        if start == context.source().len() {
+            self.context = None;
+            return None;
+        }
+
+        let parser = self.parser;
+        let result = Line::find_end(context, start).and_then(|(inner_end, outer_end)| {
+            self.pos = outer_end;
+            context.with_slice(start, inner_end, |inner_context| {
+                match_fully::<Line, P>(inner_context, parser)
+                    .map(|iter| iter.convert().into_user_type())
+            })
+        });
+        match result {
+            Ok(value) => Some(Ok(value)),
+            Err(_) => {
+                // Stop at the first error, like `lines(p)`.
+                let context = self.context.take().unwrap();
+                Some(Err(context.into_reported_error()))
+            }
+        }
+    }
+}
+
+/// An iterator that reads lines from a reader and parses each one. Returned
+/// by [`Parser::parse_lines_from`].
+pub struct ParseLines<'p, P: ?Sized, R> {
+    parser: &'p P,
+    reader: R,
+    buf: Vec<u8>,
+    /// The number of lines read so far.
+    lines_read: usize,
+    done: bool,
+}
+
+impl<'p, P: ?Sized, R> ParseLines<'p, P, R> {
+    pub(crate) fn new(parser: &'p P, reader: R) -> Self {
+        ParseLines {
+            parser,
+            reader,
+            buf: vec![],
+            lines_read: 0,
+            done: false,
+        }
+    }
+}
+
+impl<'p, P, R> Iterator for ParseLines<'p, P, R>
+where
+    P: Parser + ?Sized,
+    R: BufRead,
+{
+    type Item = Result<P::Output, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        self.buf.clear();
+        match self.reader.read_until(b'\n', &mut self.buf) {
+            Ok(0) => {
+                self.done = true;
+                None
+            }
+            Ok(_) => {
+                let line_offset = self.lines_read;
+                self.lines_read += 1;
+                let result = match std::str::from_utf8(&self.buf) {
+                    Ok(text) => {
+                        let line = text.strip_suffix('\n').unwrap_or(text);
+                        self.parser.parse(line).map_err(|err| err.with_source(text))
+                    }
+                    Err(err) => Err(ParseError::new_invalid_utf8(
+                        &String::from_utf8_lossy(&self.buf),
+                        err.valid_up_to(),
+                    )),
+                };
+                Some(result.map_err(|err| err.with_line_offset(line_offset)))
+            }
+            Err(err) => {
+                // Don't keep trying to read after an error.
+                self.done = true;
+                Some(Err(ParseError::new_io(self.lines_read + 1, &err)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, BufReader, Read};
+
+    use crate::parsers::{lines, u32, u64};
+    use crate::testing::*;
+
+    #[test]
+    fn test_parse_lines_from() {
+        let p = sequence(u32, sequence(",", u32));
+        let input = "1,2\n3,4\n5,6";
+        let values: Vec<(u32, u32)> = p
+            .parse_lines_from(input.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(values, vec![(1, 2), (3, 4), (5, 6)]);
+        assert_eq!(p.parse_lines_from(&b""[..]).count(), 0);
+
+        // Errors have line numbers in the whole input.
+        let results: Vec<_> = u64.parse_lines_from(&b"1\n2\nthree\n4\n5x"[..]).collect();
+        assert_eq!(results.len(), 5);
+        assert_eq!(
+            results[2].as_ref().unwrap_err().to_string(),
+            "expected an unsigned integer at line 3 column 1"
+        );
+        assert_eq!(
+            results[4].as_ref().unwrap_err().to_string(),
+            "extra unparsed text after match at line 5 column 2"
+        );
+        assert_eq!(*results[3].as_ref().unwrap(), 4);
+
+        let err = u64.parse_lines_from(&b"1\n1\xff\n"[..]).nth(1).unwrap();
+        assert_eq!(
+            err.unwrap_err().to_string(),
+            "input is not valid UTF-8 at line 2 column 2"
+        );
+    }
+
+    #[test]
+    fn test_parse_iter_lines() {
+        let p = sequence(u32, sequence(",", u32));
+        let values: Vec<(u32, u32)> = p
+            .parse_iter_lines("1,2\n3,4\n5,6")
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(values, vec![(1, 2), (3, 4), (5, 6)]);
+        assert_eq!(p.parse_iter_lines("").count(), 0);
+
+        // The error is the same one `lines(p)` reports, and then we stop.
+        for input in ["1,2\n3;4\n5,6\n", "1,2\n3,4x\n", "1,2\n\n"] {
+            let mut iter = p.parse_iter_lines(input);
+            assert!(iter.next().unwrap().is_ok());
+            assert_eq!(
+                iter.next().unwrap().unwrap_err().to_string(),
+                lines(p).parse(input).unwrap_err().to_string()
+            );
+            assert!(iter.next().is_none());
+        }
+    }
+
+    #[test]
+    fn test_parse_lines_from_io_error() {
+        struct Broken;
+
+        impl Read for Broken {
+            fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("disk on fire"))
+            }
+        }
+
+        let input = "1\n2\n".as_bytes().chain(Broken);
+        let results: Vec<_> = u64.parse_lines_from(BufReader::new(input)).collect();
+        assert_eq!(results.len(), 3);
+        assert_eq!(
+            results[2].as_ref().unwrap_err().to_string(),
+            "failed to read line 3: disk on fire"
+        );
+    }
+}
@@ -4,7 +4,9 @@
 
 use std::fmt::Debug;
 
+#[allow(unused_imports)]
 pub use crate::parsers::{alt, empty, opt, plus, sequence, star};
+#[allow(unused_imports)]
 pub use crate::{ParseError, ParseIter, Parser};
 
 #[track_caller]
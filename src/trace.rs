@@ -0,0 +1,224 @@
+//! Recording what the parser tried, for debugging patterns.
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::{ParseContext, ParseIter, Parser, Reported, Result};
+
+/// A record of every match attempt made while parsing, produced by
+/// [`Parser::parse_with_trace`].
+///
+/// The `Display` form is an indented tree. Each line shows one attempt to
+/// match a subpattern (or to backtrack one), the byte offset where it started,
+/// and how it turned out. Errors reported along the way appear under the
+/// attempt that reported them.
+///
+/// ```
+/// # use aoc_parse::{parser, prelude::*};
+/// let p = parser!({"a" "b", "a" "c"});
+/// let (result, trace) = p.parse_with_trace("ac");
+/// assert!(result.is_ok());
+/// println!("{trace}");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Trace {
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Attempt {
+        depth: usize,
+        kind: Kind,
+        description: String,
+        start: usize,
+        outcome: Option<Outcome>,
+    },
+    Error {
+        depth: usize,
+        location: usize,
+        message: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    Parse,
+    Backtrack,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Outcome {
+    Matched(usize),
+    Failed,
+}
+
+/// The in-progress state of a `Trace`, stored in the `ParseContext`.
+#[derive(Debug, Default)]
+pub(crate) struct Tracer {
+    trace: Trace,
+    /// Indices into `trace.nodes` of the attempts that haven't finished yet.
+    open: Vec<usize>,
+    /// Offset of the current context's source within the full input, so that
+    /// the trace shows absolute offsets even inside `line()` and `section()`.
+    pub(crate) base: usize,
+}
+
+impl Tracer {
+    fn enter(&mut self, kind: Kind, description: String, start: usize) {
+        let index = self.trace.nodes.len();
+        self.trace.nodes.push(Node::Attempt {
+            depth: self.open.len(),
+            kind,
+            description,
+            start: self.base + start,
+            outcome: None,
+        });
+        self.open.push(index);
+    }
+
+    fn exit(&mut self, end: Option<usize>) {
+        let index = self.open.pop().expect("trace exit without matching enter");
+        if let Node::Attempt { outcome, .. } = &mut self.trace.nodes[index] {
+            *outcome = Some(match end {
+                Some(end) => Outcome::Matched(self.base + end),
+                None => Outcome::Failed,
+            });
+        }
+    }
+
+    pub(crate) fn error(&mut self, location: usize, message: String) {
+        self.trace.nodes.push(Node::Error {
+            depth: self.open.len(),
+            location: self.base + location,
+            message,
+        });
+    }
+
+    pub(crate) fn finish(self) -> Trace {
+        self.trace
+    }
+}
+
+impl Display for Trace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for node in &self.nodes {
+            match node {
+                Node::Attempt {
+                    depth,
+                    kind,
+                    description,
+                    start,
+                    outcome,
+                } => {
+                    let indent = depth * 2;
+                    match kind {
+                        Kind::Parse => write!(f, "{:indent$}{description} at {start}", "")?,
+                        Kind::Backtrack => {
+                            write!(f, "{:indent$}backtrack {description} from {start}", "")?
+                        }
+                    }
+                    match outcome {
+                        Some(Outcome::Matched(end)) => writeln!(f, ": matched up to {end}")?,
+                        Some(Outcome::Failed) => writeln!(f, ": failed")?,
+                        None => writeln!(f)?,
+                    }
+                }
+                Node::Error {
+                    depth,
+                    location,
+                    message,
+                } => {
+                    let indent = depth * 2;
+                    writeln!(f, "{:indent$}error at {location}: {message}", "")?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Call `parser.parse_iter(context, start)`, recording the attempt if we're
+/// tracing.
+///
+/// Combinators use this instead of calling `parse_iter` directly on their
+/// subparsers.
+pub(crate) fn parse_iter<'parse, P>(
+    parser: &'parse P,
+    context: &mut ParseContext<'parse>,
+    start: usize,
+) -> Result<P::Iter<'parse>, Reported>
+where
+    P: Parser + ?Sized,
+{
+    match context.tracer() {
+        None => parser.parse_iter(context, start),
+        Some(tracer) => {
+            tracer.enter(Kind::Parse, parser.describe().to_string(), start);
+            let result = parser.parse_iter(context, start);
+            let end = result.as_ref().ok().map(|iter| iter.match_end());
+            if let Some(tracer) = context.tracer() {
+                tracer.exit(end);
+            }
+            result
+        }
+    }
+}
+
+/// Call `iter.backtrack(context)`, recording the attempt if we're tracing.
+/// `parser` is the parser that produced `iter`; it's only used to describe the
+/// attempt.
+pub(crate) fn backtrack<'parse, P>(
+    parser: &P,
+    iter: &mut P::Iter<'parse>,
+    context: &mut ParseContext<'parse>,
+) -> Result<(), Reported>
+where
+    P: Parser + ?Sized,
+{
+    match context.tracer() {
+        None => iter.backtrack(context),
+        Some(tracer) => {
+            tracer.enter(
+                Kind::Backtrack,
+                parser.describe().to_string(),
+                iter.match_end(),
+            );
+            let result = iter.backtrack(context);
+            let end = result.as_ref().ok().map(|()| iter.match_end());
+            if let Some(tracer) = context.tracer() {
+                tracer.exit(end);
+            }
+            result
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parsers::{lines, u32};
+    use crate::testing::*;
+
+    #[test]
+    fn test_trace_shows_alternatives() {
+        let p = alt(sequence("a", "b"), sequence("a", "c"));
+        let (result, trace) = p.parse_with_trace("ac");
+        assert!(result.is_ok());
+        let text = trace.to_string();
+        assert!(text.contains("error at 1: expected \"b\""), "{text}");
+        assert!(text.contains("\"c\" at 1: matched up to 2"), "{text}");
+    }
+
+    #[test]
+    fn test_trace_offsets_inside_lines() {
+        let p = lines(u32);
+        let (result, trace) = p.parse_with_trace("1\n2x\n");
+        assert!(result.is_err());
+        let text = trace.to_string();
+        // The error is reported inside the second line, but the trace shows
+        // its offset within the whole input.
+        assert!(
+            text.contains("error at 3: line(pattern) matched part of the line"),
+            "{text}"
+        );
+    }
+}
@@ -1,7 +1,13 @@
 //! Core traits.
 
+use std::{fmt, io::BufRead, rc::Rc, sync::Arc};
+
+use crate::parsers::LabelParser;
 use crate::types::ParserOutput;
-use crate::{ParseContext, Reported, Result};
+use crate::{
+    describe, trace, BoxedParser, Describe, IterLines, ParseContext, ParseError, ParseLines,
+    Reported, Result, Trace,
+};
 
 /// Trait implemented by all parsers.
 ///
@@ -50,23 +56,205 @@ pub trait Parser {
         start: usize,
     ) -> Result<Self::Iter<'parse>, Reported>;
 
+    /// Write a description of this parser to `f`, in the same syntax as the
+    /// pattern that would produce it, such as `lines(u64 "x" u64)`.
+    ///
+    /// This is used in trace output and error messages. The default
+    /// implementation writes the parser's type name. Custom conversions
+    /// (`=> expr`) don't appear in the description, only the patterns.
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&describe::type_name::<Self>())
+    }
+
+    /// True if this parser is `empty()`. Combinators use this to describe
+    /// themselves: `opt(p)` is written `p?`.
+    #[doc(hidden)]
+    fn is_empty_parser(&self) -> bool {
+        false
+    }
+
+    /// If matching this parser any number of times has a pattern of its own,
+    /// like `lines(p)` for `line(p)*`, write that and return `Some`.
+    #[doc(hidden)]
+    fn fmt_repeated(&self, _f: &mut fmt::Formatter<'_>) -> Option<fmt::Result> {
+        None
+    }
+
+    /// Return a value that displays as a pattern-like description of this
+    /// parser. See [`fmt_pattern`][Self::fmt_pattern].
+    ///
+    /// ```
+    /// # use aoc_parse::{parser, prelude::*};
+    /// let p = parser!(lines(u64 "x" u64 "x" u64));
+    /// assert_eq!(p.describe().to_string(), r#"lines(u64 "x" u64 "x" u64)"#);
+    /// ```
+    fn describe(&self) -> Describe<'_, Self> {
+        Describe::new(self)
+    }
+
+    /// Wrap this parser so that if it fails without matching anything, the
+    /// error message says "expected {label}". See [`label()`][crate::prelude::label].
+    fn label(self, label: &'static str) -> LabelParser<Self>
+    where
+        Self: Sized,
+    {
+        LabelParser {
+            parser: self,
+            label,
+        }
+    }
+
+    /// Like `parse`, but the input is bytes.
+    ///
+    /// The bytes must be valid UTF-8. This checks that first, then parses
+    /// them as a `str`, exactly like `parse`; it doesn't parse arbitrary
+    /// binary data. If `bytes` is not UTF-8, the error is located at the first
+    /// invalid byte, and nothing is parsed.
+    ///
+    /// Byte-oriented parsers like `any_byte` and `byte_line` are handy with
+    /// this, but any parser works.
+    ///
+    /// ```
+    /// # use aoc_parse::{parser, prelude::*};
+    /// let p = parser!(lines(byte_of(b".#")+));
+    /// assert_eq!(p.parse_bytes(b"#.\n.#\n").unwrap(), vec![vec![1, 0], vec![0, 1]]);
+    ///
+    /// let err = p.parse_bytes(b"#.\n\xff#\n").unwrap_err();
+    /// assert_eq!(err.location, 3);
+    /// ```
+    fn parse_bytes(&self, bytes: &[u8]) -> Result<Self::Output> {
+        match std::str::from_utf8(bytes) {
+            Ok(s) => self.parse(s),
+            Err(err) => Err(ParseError::new_invalid_utf8(
+                &String::from_utf8_lossy(bytes),
+                err.valid_up_to(),
+            )),
+        }
+    }
+
+    /// Read lines from `reader` and parse each one, without the newline, using
+    /// this parser. This is like `lines(p)`, but the whole input is never in
+    /// memory at once.
+    ///
+    /// The iterator produces one result per line. The [`ParseError`] for a
+    /// line that doesn't match has only that line as its `source`, but its
+    /// message gives the line number in the whole input. After an I/O error,
+    /// the iterator produces no more items.
+    ///
+    /// ```
+    /// # use aoc_parse::{parser, prelude::*};
+    /// let p = parser!(u32 "," u32);
+    /// let input = "1,2\n3,4\nfive\n";
+    /// let results: Vec<_> = p.parse_lines_from(input.as_bytes()).collect();
+    /// assert_eq!(*results[1].as_ref().unwrap(), (3, 4));
+    /// assert_eq!(
+    ///     results[2].as_ref().unwrap_err().to_string(),
+    ///     "expected an unsigned integer at line 3 column 1",
+    /// );
+    /// ```
+    fn parse_lines_from<R: BufRead>(&self, reader: R) -> ParseLines<'_, Self, R> {
+        ParseLines::new(self, reader)
+    }
+
+    /// Parse each line of `s` using this parser, like `lines(p)`, but produce
+    /// the values one at a time instead of collecting them into a `Vec`.
+    ///
+    /// If a line doesn't match, the iterator produces the same error that
+    /// `lines(p)` would, and then stops.
+    ///
+    /// ```
+    /// # use aoc_parse::{parser, prelude::*};
+    /// let p = parser!(u64 "x" u64);
+    /// let area: u64 = p
+    ///     .parse_iter_lines("2x3\n4x5\n")
+    ///     .map(|result| result.map(|(w, h)| w * h))
+    ///     .sum::<Result<u64, _>>()
+    ///     .unwrap();
+    /// assert_eq!(area, 26);
+    /// ```
+    fn parse_iter_lines<'parse>(&'parse self, s: &'parse str) -> IterLines<'parse, Self> {
+        IterLines::new(self, s)
+    }
+
+    /// Wrap this parser in a [`BoxedParser`], which has the same behavior but
+    /// a simpler type that depends only on the output type.
+    ///
+    /// ```
+    /// # use aoc_parse::{parser, prelude::*, BoxedParser};
+    /// let p: BoxedParser<(u32, u32)> = parser!(u32 "-" u32).boxed();
+    /// assert_eq!(p.parse("3-7").unwrap(), (3, 7));
+    /// ```
+    fn boxed<'a>(self) -> BoxedParser<'a, Self::Output>
+    where
+        Self: Sized + Send + Sync + 'a,
+    {
+        BoxedParser::new(self)
+    }
+
+    /// Like `parse`, but for input that's already in an `Arc<str>`. Values
+    /// produced by `str_slice()` then share `input` rather than a copy of it.
+    ///
+    /// ```
+    /// # use aoc_parse::{parser, prelude::*};
+    /// # use std::sync::Arc;
+    /// let input: Arc<str> = Arc::from("ab=1\ncd=2\n");
+    /// let p = parser!(lines(str_slice(alpha+) "=" u32));
+    /// let pairs = p.parse_shared(&input).unwrap();
+    /// assert_eq!(pairs[1].0, "cd");
+    /// ```
+    fn parse_shared(&self, input: &Arc<str>) -> Result<Self::Output> {
+        let mut ctx = ParseContext::new_shared(input);
+        match match_entire_input(self, &mut ctx) {
+            Ok(it) => Ok(it.convert().into_user_type()),
+            Err(Reported) => Err(ctx.into_reported_error()),
+        }
+    }
+
     /// Like `parse` but produce the output in its [raw form][Self::RawOutput].
     fn parse_raw(&self, s: &str) -> Result<Self::RawOutput> {
         let mut ctx = ParseContext::new(s);
-        let mut it = match self.parse_iter(&mut ctx, 0) {
-            Ok(iter) => iter,
-            Err(Reported) => return Err(ctx.into_reported_error()),
-        };
-        while it.match_end() != s.len() {
-            ctx.error_extra(it.match_end());
-            if it.backtrack(&mut ctx).is_err() {
-                return Err(ctx.into_reported_error());
-            }
+        match match_entire_input(self, &mut ctx) {
+            Ok(it) => Ok(it.convert()),
+            Err(Reported) => Err(ctx.into_reported_error()),
         }
-        Ok(it.convert())
+    }
+
+    /// Like `parse`, but also record every attempt the parser makes to match
+    /// part of the input, including backtracking and errors along the way.
+    ///
+    /// This is for debugging patterns that don't match what you expect. See
+    /// [`Trace`].
+    fn parse_with_trace(&self, s: &str) -> (Result<Self::Output>, Trace) {
+        let mut ctx = ParseContext::new_tracing(s);
+        let value = match_entire_input(self, &mut ctx)
+            .ok()
+            .map(|it| it.convert().into_user_type());
+        let (err, tracer) = ctx.into_error_and_trace();
+        let result = match value {
+            Some(value) => Ok(value),
+            None => Err(err.expect("a parse error should have been reported")),
+        };
+        (result, tracer.unwrap_or_default().finish())
     }
 }
 
+/// Match `parser` against all of the context's source text, backtracking as
+/// needed.
+fn match_entire_input<'parse, P>(
+    parser: &'parse P,
+    ctx: &mut ParseContext<'parse>,
+) -> Result<P::Iter<'parse>, Reported>
+where
+    P: Parser + ?Sized,
+{
+    let mut it = trace::parse_iter(parser, ctx, 0)?;
+    while it.match_end() != ctx.source().len() {
+        ctx.error_extra(it.match_end());
+        trace::backtrack(parser, &mut it, ctx)?;
+    }
+    Ok(it)
+}
+
 /// A parser in action. Some parsers can match in several different ways (for
 /// example, in `foo* bar` backtracking is accomplished by `foo*` first
 /// matching as much as possible, then backing off one match at a time), so
@@ -77,11 +265,24 @@ pub trait Parser {
 /// to destroy the iterator. This helps us avoid building values only to drop
 /// them later when some downstream parser fails to match, so it makes
 /// backtracking faster. It also means we don't call `.map` closures until
-/// there is a successful overall match and the values are actually needed.
+/// there is a successful overall match and the values are actually needed,
+/// with one exception: repeats convert each match of a pattern that can't
+/// backtrack as soon as they find it, so that they can drop the iterator.
 pub trait ParseIter<'parse> {
     /// The type this iterator can produce on a successful match.
     type RawOutput;
 
+    /// True if `backtrack` always fails, that is, this iterator never
+    /// produces more than one match, and matching again at the same position
+    /// would produce the same match and value. Repeats of such patterns
+    /// convert each match right away and keep only the values.
+    fn is_deterministic() -> bool
+    where
+        Self: Sized,
+    {
+        false
+    }
+
     /// Position at the end of the current match.
     fn match_end(&self) -> usize;
 
@@ -92,6 +293,9 @@ pub trait ParseIter<'parse> {
     fn backtrack(&mut self, context: &mut ParseContext<'parse>) -> Result<(), Reported>;
 
     /// Convert the matched text to a Rust value.
+    ///
+    /// Once this is called, it may be called again only after a call to
+    /// `backtrack`.
     fn convert(&self) -> Self::RawOutput;
 }
 
@@ -102,7 +306,8 @@ where
     type Output = P::Output;
     type RawOutput = P::RawOutput;
 
-    type Iter<'parse> = P::Iter<'parse>
+    type Iter<'parse>
+        = P::Iter<'parse>
     where
         P: 'parse,
         'a: 'parse;
@@ -114,4 +319,58 @@ where
     ) -> Result<Self::Iter<'parse>, Reported> {
         <P as Parser>::parse_iter(self, context, start)
     }
+
+    fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        <P as Parser>::fmt_pattern(self, f)
+    }
+
+    fn is_empty_parser(&self) -> bool {
+        <P as Parser>::is_empty_parser(self)
+    }
+
+    fn fmt_repeated(&self, f: &mut fmt::Formatter<'_>) -> Option<fmt::Result> {
+        <P as Parser>::fmt_repeated(self, f)
+    }
 }
+
+macro_rules! impl_parser_for_pointer {
+    ( $( $ptr:ident )* ) => {
+        $(
+            /// A pointer to a parser is a parser. This makes it easy to share a
+            /// parser that isn't `Copy`.
+            impl<P> Parser for $ptr<P>
+            where
+                P: Parser + ?Sized,
+            {
+                type Output = P::Output;
+                type RawOutput = P::RawOutput;
+
+                type Iter<'parse> = P::Iter<'parse>
+                where
+                    P: 'parse;
+
+                fn parse_iter<'parse>(
+                    &'parse self,
+                    context: &mut ParseContext<'parse>,
+                    start: usize,
+                ) -> Result<Self::Iter<'parse>, Reported> {
+                    <P as Parser>::parse_iter(self, context, start)
+                }
+
+                fn fmt_pattern(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    <P as Parser>::fmt_pattern(self, f)
+                }
+
+                fn is_empty_parser(&self) -> bool {
+                    <P as Parser>::is_empty_parser(self)
+                }
+
+                fn fmt_repeated(&self, f: &mut fmt::Formatter<'_>) -> Option<fmt::Result> {
+                    <P as Parser>::fmt_repeated(self, f)
+                }
+            }
+        )*
+    };
+}
+
+impl_parser_for_pointer!(Box Rc Arc);
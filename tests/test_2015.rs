@@ -182,9 +182,9 @@ fn day11() {
 #[test]
 fn day13() {
     let p = parser!(lines(
-        string(alpha+) " would " { "gain" => 1, "lose" => -1 }
-        " " u32 " happiness units by sitting next to " string(alpha+) "."
-        ));
+    string(alpha+) " would " { "gain" => 1, "lose" => -1 }
+    " " u32 " happiness units by sitting next to " string(alpha+) "."
+    ));
 
     assert_parse_eq(
         p,
@@ -92,10 +92,26 @@ fn day4() {
             totally-real-room-200[decoy]\n\
         ",
         vec![
-            ("aaaaa-bbb-z-y-x".to_string(), 123, vec!['a', 'b', 'x', 'y', 'z']),
-            ("a-b-c-d-e-f-g-h".to_string(), 987, vec!['a', 'b', 'c', 'd', 'e']),
-            ("not-a-real-room".to_string(), 404, vec!['o', 'a', 'r', 'e', 'l']),
-            ("totally-real-room".to_string(), 200, vec!['d', 'e', 'c', 'o', 'y']),
+            (
+                "aaaaa-bbb-z-y-x".to_string(),
+                123,
+                vec!['a', 'b', 'x', 'y', 'z'],
+            ),
+            (
+                "a-b-c-d-e-f-g-h".to_string(),
+                987,
+                vec!['a', 'b', 'c', 'd', 'e'],
+            ),
+            (
+                "not-a-real-room".to_string(),
+                404,
+                vec!['o', 'a', 'r', 'e', 'l'],
+            ),
+            (
+                "totally-real-room".to_string(),
+                200,
+                vec!['d', 'e', 'c', 'o', 'y'],
+            ),
         ],
     );
 }
@@ -37,6 +37,7 @@ C Z
 }
 
 #[test]
+#[allow(unused_parens)]
 fn day3() {
     let input = "\
 vJrwpWtwJgWrhcsFMMfFFhFp
@@ -64,3 +64,44 @@ fn test_function_int_argument() {
     let active = 4;
     assert_parse_eq(parser!(player(active) ": " u64), "posh: 0", 0);
 }
+
+#[test]
+fn test_boxed_and_shared_parsers() {
+    use std::{rc::Rc, sync::Arc};
+
+    use aoc_parse::BoxedParser;
+
+    // A table of parsers chosen at runtime, all with the same type.
+    let days: Vec<BoxedParser<'static, Vec<i64>>> = vec![
+        parser!(lines(i64)).boxed(),
+        parser!(line(repeat_sep(i64, ","))).boxed(),
+        parser!(line(x:i64 "x" y:i64 => vec![x, y])).boxed(),
+    ];
+    assert_parse_eq(&days[0], "1\n-2\n", vec![1, -2]);
+    assert_parse_eq(&days[1], "3,4,5\n", vec![3, 4, 5]);
+    assert_parse_eq(&days[2], "6x7\n", vec![6, 7]);
+
+    // Boxed parsers can be used inside other patterns.
+    let day = days[1].clone();
+    assert_parse_eq(parser!("n\n" day), "n\n8,9\n", vec![8, 9]);
+
+    // So can `Rc` and `Arc` pointers to parsers.
+    let rc = Rc::new(parser!(u64 "!"));
+    let arc = Arc::new(parser!(string(alpha+)));
+    assert_parse_eq(parser!(rc " " arc), "12! ok", (12, "ok".to_string()));
+    assert_parse_eq(Box::new(parser!(u8)), "255", 255);
+}
+
+#[test]
+fn test_rational_types_are_reexported() {
+    use aoc_parse::num_rational::{BigRational, Ratio};
+
+    let p = parser!(lines(decimal));
+    assert_parse_eq(
+        p,
+        "0.25\n1.0000000000000000000\n",
+        vec![Ratio::new(1, 4), Ratio::from_integer(1)],
+    );
+    let big: BigRational = big_decimal.parse("1e30").unwrap();
+    assert_eq!(big.to_string(), format!("1{}", "0".repeat(30)));
+}
@@ -132,7 +132,7 @@ fn test_repeat() {
 #[test]
 fn test_repeat_sep() {
     let p = parser!("lucky numbers: " (u32 ", ")* u32);
-    assert_parse_error(p, "lucky numbers: ", "expected u32");
+    assert_parse_error(p, "lucky numbers: ", "expected an unsigned integer");
 }
 
 #[test]
@@ -191,6 +191,7 @@ mod names_and_scopes {
 }
 
 #[test]
+#[allow(clippy::needless_borrows_for_generic_args)]
 fn test_chars() {
     assert_parse_eq(parser!('A' 'b' 'c'), "Abc", ());
     assert_no_parse(parser!('Q'), "q");
@@ -312,3 +313,160 @@ fn test_rule_set() {
     assert_parse_eq(&calc, "2+2", 4);
     assert_parse_eq(&calc, "2+(3*(4+5+2))", 35);
 }
+
+#[test]
+fn test_rule_set_sharing() {
+    fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+    // A rule set that captures something that isn't `Sync` still builds; it
+    // just can't be shared across threads.
+    let k: &'static std::cell::Cell<u32> = Box::leak(Box::new(std::cell::Cell::new(3)));
+    let p = parser!(rule a: u32 = x:u32 => x * k.get(); a);
+    assert_parse_eq(&p, "2", 6);
+    k.set(5);
+    assert_parse_eq(&p, "2", 10);
+
+    let p = parser!(rule a: u32 = x:u32 => x * 3; a);
+    assert_send_sync(&p);
+    std::thread::scope(|s| {
+        s.spawn(|| assert_parse_eq(&p, "2", 6));
+    });
+}
+
+#[test]
+fn test_try_map() {
+    let p = parser!(x:i32 =>? u8::try_from(x));
+    assert_parse_eq(p, "17", 17u8);
+    assert_parse_error(
+        p,
+        "-17",
+        r#"failed to convert "-17": out of range integral type conversion attempted at line 1 column 1"#,
+    );
+
+    // A failed conversion makes the pattern backtrack and try shorter matches.
+    let p = parser!(
+        (d:digit+ =>? if d.len() == 2 { Ok(d) } else { Err("need two digits") })
+        digit*
+    );
+    assert_parse_eq(p, "12345", (vec![1, 2], vec![3, 4, 5]));
+
+    // Works in alternation arms.
+    let p = parser!({
+        n:u32 =>? if n % 2 == 0 { Ok(n / 2) } else { Err("odd") },
+        "half of " n:u32 => n,
+    });
+    assert_parse_eq(p, "18", 9);
+    assert_parse_eq(p, "half of 7", 7);
+    assert_parse_error(p, "7", "odd at line 1 column 1");
+}
+
+#[test]
+fn test_try_map_runs_once_per_match() {
+    // The closure runs once per match it's tried on, not again to convert.
+    let calls = std::cell::Cell::new(0);
+    let p = parser!(lines(x:u32 =>? {
+        calls.set(calls.get() + 1);
+        Ok::<_, &str>(x)
+    }));
+    assert_eq!(p.parse("1\n2\n").unwrap(), vec![1, 2]);
+    assert_eq!(calls.get(), 2);
+
+    // So a stateful check still works when an outer parser converts the same
+    // match twice, as `then` does after backtracking.
+    let seen = std::cell::RefCell::new(std::collections::HashSet::new());
+    let item =
+        parser!(x:u32 " " =>? if seen.borrow_mut().insert(x) { Ok(x) } else { Err("duplicate") });
+    let p = then(parser!(item*), |v| {
+        let n = if v.len() == 3 { 5 } else { 1 };
+        parser!(repeat_n(u32 " ", n) "!")
+    });
+    assert_eq!(p.parse("1 2 3 !").unwrap(), vec![3]);
+}
+
+#[test]
+fn test_then() {
+    let p = parser!(n:usize ": " =>> repeat_sep_n(alpha, " ", n));
+    assert_parse_eq(&p, "3: a b c", vec!['a', 'b', 'c']);
+    assert_parse_error(
+        &p,
+        "2: a b c",
+        "extra unparsed text after match at line 1 column 7",
+    );
+
+    // Labels are in scope in the expression, which can be another `parser!`.
+    let p = parser!(lines(w:usize "x" h:usize ":" =>> parser!(repeat_n(string(repeat_n(char_of(".#"), w) "/"), h))));
+    assert_parse_eq(
+        &p,
+        "2x1:.#/\n1x2:#/./\n",
+        vec![
+            vec![".#/".to_string()],
+            vec!["#/".to_string(), "./".to_string()],
+        ],
+    );
+
+    // Works in alternation arms.
+    let p = parser!({
+        "none" => vec![],
+        n:usize " " =>> repeat_n(digit, n),
+    });
+    assert_parse_eq(&p, "none", vec![]);
+    assert_parse_eq(&p, "2 78", vec![7, 8]);
+
+    // The second parser can be a rule set, built fresh for each line and
+    // backtracked into.
+    let p = parser!(lines(n:usize ": " =>> parser!(
+        rule item: Vec<char> = repeat_n(alpha, n);
+        item+ "!"
+    )));
+    assert_parse_eq(
+        &p,
+        "1: ab!\n2: abcd!\n",
+        vec![
+            vec![vec!['a'], vec!['b']],
+            vec![vec!['a', 'b'], vec!['c', 'd']],
+        ],
+    );
+    assert_parse_error(&p, "2: abc!\n", "expected a letter at line 1 column 7");
+}
+
+aoc_parse::enum_parser! {
+    #[parse(rename_all = "words")]
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Action {
+        TurnOn,
+        TurnOff,
+        Toggle,
+    }
+}
+
+aoc_parse::enum_parser! {
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Register {
+        A,
+        B,
+    }
+}
+
+#[test]
+fn test_enum_parser() {
+    let p = parser!(lines(Action " " u32 "," u32));
+    assert_parse_eq(
+        &p,
+        "turn on 1,2\ntoggle 3,4\nturn off 5,6\n",
+        vec![
+            (Action::TurnOn, 1, 2),
+            (Action::Toggle, 3, 4),
+            (Action::TurnOff, 5, 6),
+        ],
+    );
+    assert_parse_error(
+        &p,
+        "turn 1,2\n",
+        r#"expected one of "turn on", "turn off", "toggle" at line 1 column 1"#,
+    );
+    assert_eq!(Action.describe().to_string(), "Action");
+
+    let p = parser!(r:Register ", " n:i32 => (r, n));
+    assert_parse_eq(p, "B, -3", (Register::B, -3));
+    assert_no_parse(p, "b, -3");
+}